@@ -0,0 +1,64 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use itertools::Itertools;
+use std::collections::HashSet;
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/rucksack.rs"]
+mod rucksack;
+
+// Previous implementation, kept as a baseline for the bitmask version
+fn scan_sum_of_priorities(data: &str) -> i32 {
+    data.lines()
+        .map(|line| {
+            let half_len = line.len() / 2;
+            let first_comp = &line[0..half_len];
+            let second_comp = &line[half_len..];
+            first_comp
+                .chars()
+                .filter(|c| second_comp.contains(*c))
+                .unique()
+                .map(rucksack::item_to_priority)
+                .sum::<i32>()
+        })
+        .sum()
+}
+
+fn hashset_sum_of_group_priorities(data: &str) -> i32 {
+    data.lines()
+        .chunks(3)
+        .into_iter()
+        .filter_map(|mut chunk| {
+            let mut set: HashSet<_> = chunk.next()?.chars().collect();
+            for rucksacks in chunk {
+                set.retain(|e| rucksacks.chars().contains(e))
+            }
+            set.into_iter().next()
+        })
+        .map(rucksack::item_to_priority)
+        .sum()
+}
+
+fn bench_priorities(c: &mut Criterion) {
+    let data = include_str!("../input.txt");
+
+    let mut group = c.benchmark_group("sum_of_priorities");
+    group.bench_function("scan", |b| {
+        b.iter(|| scan_sum_of_priorities(black_box(data)))
+    });
+    group.bench_function("bitmask", |b| {
+        b.iter(|| rucksack::sum_of_priorities(black_box(data), rucksack::ParseMode::Strict))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("sum_of_group_priorities");
+    group.bench_function("hashset", |b| {
+        b.iter(|| hashset_sum_of_group_priorities(black_box(data)))
+    });
+    group.bench_function("bitmask", |b| {
+        b.iter(|| rucksack::sum_of_group_priorities(black_box(data), rucksack::ParseMode::Strict))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_priorities);
+criterion_main!(benches);
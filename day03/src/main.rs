@@ -1,55 +1,41 @@
-use itertools::Itertools;
-use std::collections::HashSet;
+mod rucksack;
+
+use rucksack::{
+    common_items_per_group, sum_of_group_priorities, sum_of_priorities, ItemMask, ParseMode,
+};
 use std::fs;
 
 fn main() {
-    let data = fs::read_to_string("input.txt").unwrap().replace("\r", "");
-    println!("sum of priorities: {}", sum_of_priorities(&data));
-    println!(
-        "sum of group priorities: {}",
-        sum_of_group_priorities(&data)
-    );
-}
+    let data = fs::read_to_string("input.txt").unwrap().replace('\r', "");
 
-fn sum_of_priorities(data: &str) -> i32 {
-    data.split("\n")
-        .map(|line| {
-            let len = line.len();
-            let half_len = len / 2;
-            let first_comp = &line[0..half_len];
-            let second_comp = &line[half_len..len];
-            first_comp
-                .chars()
-                .filter(|c| second_comp.contains(*c))
-                .unique()
-                .map(|c| item_to_priority(c))
-                .sum::<i32>()
-        })
-        .sum::<i32>()
-}
-
-fn sum_of_group_priorities(data: &str) -> i32 {
-    data.split("\n")
-        .chunks(3)
-        .into_iter()
-        .filter_map(|mut chunk| {
-            let mut set: HashSet<_> = chunk.next()?.chars().collect();
-            for rucksacks in chunk {
-                set.retain(|e| rucksacks.chars().contains(e))
-            }
-            set.into_iter().next()
-        })
-        .map(|c| item_to_priority(c))
-        .sum()
-}
+    // Fall back to lenient parsing to still get answers out of a damaged input
+    let mode = match sum_of_priorities(&data, ParseMode::Strict)
+        .and(sum_of_group_priorities(&data, ParseMode::Strict))
+    {
+        Ok(_) => ParseMode::Strict,
+        Err(error) => {
+            println!("invalid input: {:?}", error);
+            ParseMode::Lenient
+        }
+    };
 
-fn item_to_priority(c: char) -> i32 {
-    assert!(c.is_alphabetic());
-    if c.is_lowercase() {
-        (c as u8 - b'a' + 1) as i32
-    } else {
-        (c as u8 - b'A' + 27) as i32
+    let priorities = sum_of_priorities(&data, mode).unwrap();
+    let group_priorities = sum_of_group_priorities(&data, mode).unwrap();
+    let badges = common_items_per_group(&data, 3, mode).unwrap();
+    for warning in priorities.warnings.iter().chain(&badges.warnings) {
+        println!("warning: {:?}", warning);
     }
+
+    println!("sum of priorities: {}", priorities.value);
+    println!("sum of group priorities: {}", group_priorities.value);
+    println!(
+        "group badges: {}",
+        badges
+            .value
+            .into_iter()
+            .flat_map(ItemMask::items)
+            .collect::<String>()
+    );
 }
 
 #[cfg(test)]
@@ -63,19 +49,21 @@ wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
 ttgJtRGJQctTZtZT
 CrZsJsPPZsGzwwsLwLmpwMDw";
 
-    #[test]
-    fn test_item_priority() {
-        assert_eq!(item_to_priority('c'), 3);
-        assert_eq!(item_to_priority('C'), 29);
-    }
-
     #[test]
     fn test_part1() {
-        assert_eq!(sum_of_priorities(EXAMPLE), 157);
+        assert_eq!(
+            sum_of_priorities(EXAMPLE, ParseMode::Strict).unwrap().value,
+            157
+        );
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(sum_of_group_priorities(EXAMPLE), 70);
+        assert_eq!(
+            sum_of_group_priorities(EXAMPLE, ParseMode::Strict)
+                .unwrap()
+                .value,
+            70
+        );
     }
 }
@@ -0,0 +1,374 @@
+// Set of items stored as a 52-bit mask.
+// Bit `n` is set when the item with priority `n + 1` is present,
+// so `a..=z` use bits 0 to 25 and `A..=Z` use bits 26 to 51.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ItemMask(u64);
+
+impl ItemMask {
+    // Items are ASCII letters, `parse_rucksacks` skips anything else
+    fn from_items(items: &str) -> ItemMask {
+        ItemMask(
+            items
+                .chars()
+                .fold(0, |mask, c| mask | 1 << (item_to_priority(c) - 1)),
+        )
+    }
+
+    pub fn intersection(self, other: ItemMask) -> ItemMask {
+        ItemMask(self.0 & other.0)
+    }
+
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn items(self) -> impl Iterator<Item = char> {
+        (0..52)
+            .filter(move |bit| self.0 & (1 << bit) != 0)
+            .map(|bit| priority_to_item(bit + 1))
+    }
+
+    pub fn priority_sum(self) -> i32 {
+        (0..52)
+            .filter(|bit| self.0 & (1 << bit) != 0)
+            .map(|bit| bit + 1)
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    // Fail on the first problem found in the input
+    Strict,
+    // Record problems as warnings and compute with what is usable
+    Lenient,
+}
+
+// Problems found in the rucksacks list, `line` is 1-based.
+// For group errors `line` is the line of the first rucksack of the group.
+#[derive(Debug, PartialEq)]
+pub enum RucksackError {
+    EmptyLine { line: usize },
+    OddLength { line: usize, len: usize },
+    InvalidItem { line: usize, item: char },
+    NoBadge { line: usize },
+    MultipleBadges { line: usize, badges: String },
+    PartialGroup { line: usize, size: usize },
+}
+
+// A value computed from the rucksacks and the problems
+// that were tolerated in lenient mode to compute it.
+#[derive(Debug, PartialEq)]
+pub struct Checked<T> {
+    pub value: T,
+    pub warnings: Vec<RucksackError>,
+}
+
+impl<T> Checked<T> {
+    fn map<U>(self, f: impl FnOnce(T) -> U) -> Checked<U> {
+        Checked {
+            value: f(self.value),
+            warnings: self.warnings,
+        }
+    }
+}
+
+struct Diagnostics {
+    mode: ParseMode,
+    warnings: Vec<RucksackError>,
+}
+
+impl Diagnostics {
+    fn new(mode: ParseMode) -> Diagnostics {
+        Diagnostics {
+            mode,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn report(&mut self, error: RucksackError) -> Result<(), RucksackError> {
+        match self.mode {
+            ParseMode::Strict => Err(error),
+            ParseMode::Lenient => {
+                self.warnings.push(error);
+                Ok(())
+            }
+        }
+    }
+
+    fn into_checked<T>(self, value: T) -> Checked<T> {
+        Checked {
+            value,
+            warnings: self.warnings,
+        }
+    }
+}
+
+// A rucksack that only contains valid items
+struct Rucksack {
+    line: usize,
+    items: String,
+}
+
+// Empty lines are skipped and invalid items are dropped in lenient mode
+fn parse_rucksacks(
+    data: &str,
+    diagnostics: &mut Diagnostics,
+) -> Result<Vec<Rucksack>, RucksackError> {
+    let mut rucksacks = Vec::new();
+    for (idx, line) in data.lines().enumerate() {
+        let line_number = idx + 1;
+        if line.is_empty() {
+            diagnostics.report(RucksackError::EmptyLine { line: line_number })?;
+            continue;
+        }
+        let mut items = String::with_capacity(line.len());
+        for item in line.chars() {
+            if item.is_ascii_alphabetic() {
+                items.push(item);
+            } else {
+                diagnostics.report(RucksackError::InvalidItem {
+                    line: line_number,
+                    item,
+                })?;
+            }
+        }
+        rucksacks.push(Rucksack {
+            line: line_number,
+            items,
+        });
+    }
+    Ok(rucksacks)
+}
+
+// Intersection of all the masks, or an empty mask if there is none.
+fn common_items(mut masks: impl Iterator<Item = ItemMask>) -> ItemMask {
+    match masks.next() {
+        Some(first) => masks.fold(first, ItemMask::intersection),
+        None => ItemMask::default(),
+    }
+}
+
+// Split every rucksack into `compartments` equally sized compartments
+// and return the items shared by all of them, one mask per rucksack.
+// In lenient mode the items left over by an uneven split are ignored.
+pub fn common_items_per_rucksack(
+    data: &str,
+    compartments: usize,
+    mode: ParseMode,
+) -> Result<Checked<Vec<ItemMask>>, RucksackError> {
+    assert!(compartments > 0);
+    let mut diagnostics = Diagnostics::new(mode);
+    let mut common = Vec::new();
+    for rucksack in parse_rucksacks(data, &mut diagnostics)? {
+        let items = &rucksack.items;
+        if items.len() % compartments != 0 {
+            diagnostics.report(RucksackError::OddLength {
+                line: rucksack.line,
+                len: items.len(),
+            })?;
+        }
+        let len = items.len() / compartments;
+        common.push(common_items(
+            (0..compartments).map(|i| ItemMask::from_items(&items[i * len..(i + 1) * len])),
+        ));
+    }
+    Ok(diagnostics.into_checked(common))
+}
+
+// Group rucksacks by `group_size` and return the items carried by every
+// elf of the group, one mask per group.
+// Each group must share exactly one badge, in lenient mode the mask of
+// an incomplete group or of a group without a single badge is empty.
+pub fn common_items_per_group(
+    data: &str,
+    group_size: usize,
+    mode: ParseMode,
+) -> Result<Checked<Vec<ItemMask>>, RucksackError> {
+    assert!(group_size > 0);
+    let mut diagnostics = Diagnostics::new(mode);
+    let mut common = Vec::new();
+    for group in parse_rucksacks(data, &mut diagnostics)?.chunks(group_size) {
+        let line = group[0].line;
+        if group.len() < group_size {
+            diagnostics.report(RucksackError::PartialGroup {
+                line,
+                size: group.len(),
+            })?;
+        }
+        let badges = common_items(group.iter().map(|r| ItemMask::from_items(&r.items)));
+        match badges.count() {
+            0 => diagnostics.report(RucksackError::NoBadge { line })?,
+            1 => {}
+            _ => diagnostics.report(RucksackError::MultipleBadges {
+                line,
+                badges: badges.items().collect(),
+            })?,
+        }
+        if group.len() == group_size && badges.count() == 1 {
+            common.push(badges);
+        } else {
+            common.push(ItemMask::default());
+        }
+    }
+    Ok(diagnostics.into_checked(common))
+}
+
+pub fn sum_of_priorities(data: &str, mode: ParseMode) -> Result<Checked<i32>, RucksackError> {
+    Ok(common_items_per_rucksack(data, 2, mode)?
+        .map(|common| common.into_iter().map(ItemMask::priority_sum).sum()))
+}
+
+pub fn sum_of_group_priorities(data: &str, mode: ParseMode) -> Result<Checked<i32>, RucksackError> {
+    Ok(common_items_per_group(data, 3, mode)?
+        .map(|common| common.into_iter().map(ItemMask::priority_sum).sum()))
+}
+
+pub fn item_to_priority(c: char) -> i32 {
+    assert!(c.is_ascii_alphabetic());
+    if c.is_lowercase() {
+        (c as u8 - b'a' + 1) as i32
+    } else {
+        (c as u8 - b'A' + 27) as i32
+    }
+}
+
+pub fn priority_to_item(priority: i32) -> char {
+    assert!((1..=52).contains(&priority));
+    if priority <= 26 {
+        (b'a' + priority as u8 - 1) as char
+    } else {
+        (b'A' + priority as u8 - 27) as char
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static EXAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw";
+
+    #[test]
+    fn test_item_priority() {
+        assert_eq!(item_to_priority('c'), 3);
+        assert_eq!(item_to_priority('C'), 29);
+        assert_eq!(priority_to_item(3), 'c');
+        assert_eq!(priority_to_item(29), 'C');
+    }
+
+    #[test]
+    fn test_item_mask() {
+        let mask = ItemMask::from_items("zaZAa");
+        assert_eq!(mask.items().collect::<String>(), "azAZ");
+        assert_eq!(mask.priority_sum(), 1 + 26 + 27 + 52);
+        assert_eq!(
+            mask.intersection(ItemMask::from_items("bB")),
+            ItemMask::default()
+        );
+    }
+
+    #[test]
+    fn test_common_items_per_rucksack() {
+        let common = common_items_per_rucksack(EXAMPLE, 2, ParseMode::Strict).unwrap();
+        assert!(common.warnings.is_empty());
+        let items: String = common.value.into_iter().flat_map(ItemMask::items).collect();
+        assert_eq!(items, "pLPvts");
+
+        // Thirds of "aabbab" are "aa", "bb" and "ab": nothing is shared
+        let common = common_items_per_rucksack("abcabc\naabbab", 3, ParseMode::Strict).unwrap();
+        assert_eq!(common.value.len(), 2);
+        assert_eq!(common.value[0].items().collect::<String>(), "");
+        assert_eq!(common.value[1], ItemMask::default());
+        let common = common_items_per_rucksack("abab", 2, ParseMode::Strict).unwrap();
+        assert_eq!(common.value[0].items().collect::<String>(), "ab");
+    }
+
+    #[test]
+    fn test_common_items_per_group() {
+        let common = common_items_per_group(EXAMPLE, 3, ParseMode::Strict).unwrap();
+        let items: String = common.value.into_iter().flat_map(ItemMask::items).collect();
+        assert_eq!(items, "rZ");
+
+        let common = common_items_per_group(EXAMPLE, 6, ParseMode::Lenient).unwrap();
+        assert_eq!(common.value, vec![ItemMask::default()]);
+        assert_eq!(common.warnings, vec![RucksackError::NoBadge { line: 1 }]);
+    }
+
+    #[test]
+    fn test_rucksack_errors() {
+        assert_eq!(
+            common_items_per_rucksack("abab\n\nabab", 2, ParseMode::Strict).unwrap_err(),
+            RucksackError::EmptyLine { line: 2 }
+        );
+        assert_eq!(
+            common_items_per_rucksack("abab\nabcab", 2, ParseMode::Strict).unwrap_err(),
+            RucksackError::OddLength { line: 2, len: 5 }
+        );
+        assert_eq!(
+            common_items_per_rucksack("abéab", 2, ParseMode::Strict).unwrap_err(),
+            RucksackError::InvalidItem {
+                line: 1, item: 'é'
+            }
+        );
+        assert_eq!(
+            common_items_per_group("ab\ncd", 2, ParseMode::Strict).unwrap_err(),
+            RucksackError::NoBadge { line: 1 }
+        );
+        assert_eq!(
+            common_items_per_group("ab\nab", 2, ParseMode::Strict).unwrap_err(),
+            RucksackError::MultipleBadges {
+                line: 1,
+                badges: "ab".to_string()
+            }
+        );
+        assert_eq!(
+            common_items_per_group("ab\nbc\nbd", 2, ParseMode::Strict).unwrap_err(),
+            RucksackError::PartialGroup { line: 3, size: 1 }
+        );
+    }
+
+    #[test]
+    fn test_lenient_mode() {
+        let common = common_items_per_rucksack("a1a\n\nabcab", 2, ParseMode::Lenient).unwrap();
+        assert_eq!(
+            common.warnings,
+            vec![
+                RucksackError::InvalidItem { line: 1, item: '1' },
+                RucksackError::EmptyLine { line: 2 },
+                RucksackError::OddLength { line: 3, len: 5 },
+            ]
+        );
+        let items: Vec<String> = common
+            .value
+            .into_iter()
+            .map(|mask| mask.items().collect())
+            .collect();
+        assert_eq!(items, vec!["a", "a"]);
+
+        let common = common_items_per_group("ab\nbc\nbd", 2, ParseMode::Lenient).unwrap();
+        assert_eq!(
+            common.warnings,
+            vec![
+                RucksackError::PartialGroup { line: 3, size: 1 },
+                RucksackError::MultipleBadges {
+                    line: 3,
+                    badges: "bd".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            common.value,
+            vec![ItemMask::from_items("b"), ItemMask::default()]
+        );
+
+        // The trailing incomplete group doesn't count
+        let sum = sum_of_group_priorities(&format!("{}\nbd", EXAMPLE), ParseMode::Lenient).unwrap();
+        assert_eq!(sum.value, 18 + 52);
+        assert_eq!(sum.warnings.len(), 2);
+    }
+}
@@ -0,0 +1,251 @@
+use interval::{Interval, IntervalSet};
+use std::collections::BTreeMap;
+
+// Characters used by the heatmap, from no elf to the most assigned section
+const HEATMAP_SHADES: &[u8] = b" .:-=+*#%@";
+
+// Coverage of the camp by all the assignments
+#[derive(Debug, PartialEq)]
+pub struct CoverageReport {
+    // Number of elves assigned to each run of sections.
+    // Runs are sorted and sections without any elf are omitted.
+    profile: Vec<(Interval, usize)>,
+}
+
+impl CoverageReport {
+    pub fn new<'a>(ranges: impl IntoIterator<Item = &'a Interval>) -> CoverageReport {
+        // Sweep over the bounds: an elf enters at `start` and leaves after `end`
+        let mut deltas = BTreeMap::<u64, i64>::new();
+        for range in ranges {
+            *deltas.entry(range.start).or_default() += 1;
+            if let Some(after) = range.end.checked_add(1) {
+                *deltas.entry(after).or_default() -= 1;
+            }
+        }
+
+        let mut profile: Vec<(Interval, usize)> = Vec::new();
+        let mut count = 0;
+        let mut bounds = deltas.into_iter().peekable();
+        while let Some((section, delta)) = bounds.next() {
+            count += delta;
+            if count == 0 {
+                continue;
+            }
+            let end = match bounds.peek() {
+                Some((next, _)) => next - 1,
+                None => u64::MAX,
+            };
+            match profile.last_mut() {
+                // Elves entering and leaving at the same section don't start a new run
+                Some((run, last_count))
+                    if *last_count == count as usize && run.end + 1 == section =>
+                {
+                    run.end = end
+                }
+                _ => profile.push((Interval::new(section, end).unwrap(), count as usize)),
+            }
+        }
+        CoverageReport { profile }
+    }
+
+    pub fn covered(&self) -> IntervalSet {
+        self.profile.iter().map(|(run, _)| *run).collect()
+    }
+
+    // Number of distinct sections with at least one elf
+    pub fn covered_sections(&self) -> u128 {
+        self.profile.iter().map(|(run, _)| run.len()).sum()
+    }
+
+    pub fn covered_by_more_than(&self, elves: usize) -> IntervalSet {
+        self.profile
+            .iter()
+            .filter(|(_, count)| *count > elves)
+            .map(|(run, _)| *run)
+            .collect()
+    }
+
+    // Sections without any elf between the lowest and highest assigned ones
+    pub fn gaps(&self) -> IntervalSet {
+        match (self.profile.first(), self.profile.last()) {
+            (Some((first, _)), Some((last, _))) => {
+                IntervalSet::from(Interval::new(first.start, last.end).unwrap())
+                    .difference(&self.covered())
+            }
+            _ => IntervalSet::new(),
+        }
+    }
+
+    pub fn max_assignments(&self) -> usize {
+        self.profile
+            .iter()
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Number of elves of the most assigned section in `cell`
+    fn cell_assignments(&self, cell: &Interval) -> usize {
+        let first = self
+            .profile
+            .partition_point(|(run, _)| run.end < cell.start);
+        self.profile[first..]
+            .iter()
+            .take_while(|(run, _)| run.start <= cell.end)
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Render the number of elves per section, one character per cell,
+    // `width` cells per row. When the sections don't fit in `max_rows` rows,
+    // each cell groups several sections and shows the most assigned one.
+    // Nothing is rendered without any section or without room for a cell.
+    pub fn render_heatmap(&self, width: usize, max_rows: usize) -> String {
+        let (Some((first, _)), Some((last, _))) = (self.profile.first(), self.profile.last())
+        else {
+            return String::new();
+        };
+        if width == 0 || max_rows == 0 {
+            return String::new();
+        }
+        let span = Interval::new(first.start, last.end).unwrap();
+        let max_cells = (width * max_rows) as u128;
+        let cell_len = span.len().div_ceil(max_cells).max(1);
+        let max = self.max_assignments();
+        let label_width = span.end.to_string().len();
+
+        // Offsets from the start of the span are below its length, and
+        // only the sections themselves are known to fit in a u64
+        let section = |offset: u128| span.start + offset as u64;
+        let cells = span.len().div_ceil(cell_len);
+        let mut heatmap = String::new();
+        for row in 0..cells.div_ceil(width as u128) {
+            let row_offset = row * width as u128 * cell_len;
+            let shades: String = (0..width as u128)
+                .map(|col| row_offset + col * cell_len)
+                .take_while(|&offset| offset < span.len())
+                .map(|offset| {
+                    let cell_start = section(offset);
+                    let cell_end = section((offset + cell_len).min(span.len()) - 1);
+                    let count =
+                        self.cell_assignments(&Interval::new(cell_start, cell_end).unwrap());
+                    HEATMAP_SHADES[(count * (HEATMAP_SHADES.len() - 1)).div_ceil(max)] as char
+                })
+                .collect();
+            heatmap += &format!("{:>label_width$} |{}|\n", section(row_offset), shades);
+        }
+        heatmap += &format!(
+            "{} section(s) per cell, '{}' is {} elves\n",
+            cell_len,
+            HEATMAP_SHADES[HEATMAP_SHADES.len() - 1] as char,
+            max
+        );
+        heatmap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(ranges: &[(u64, u64)]) -> Vec<Interval> {
+        ranges
+            .iter()
+            .map(|&(start, end)| Interval::new(start, end).unwrap())
+            .collect()
+    }
+
+    fn example_report() -> CoverageReport {
+        let ranges = ranges(&[
+            (2, 4),
+            (6, 8),
+            (2, 3),
+            (4, 5),
+            (5, 7),
+            (7, 9),
+            (2, 8),
+            (3, 7),
+            (6, 6),
+            (4, 6),
+            (2, 6),
+            (4, 8),
+        ]);
+        CoverageReport::new(&ranges)
+    }
+
+    #[test]
+    fn test_profile() {
+        let report = example_report();
+        assert_eq!(
+            report
+                .profile
+                .iter()
+                .map(|(run, count)| (run.start, run.end, *count))
+                .collect::<Vec<_>>(),
+            vec![
+                (2, 2, 4),
+                (3, 3, 5),
+                (4, 5, 7),
+                (6, 6, 8),
+                (7, 7, 6),
+                (8, 8, 4),
+                (9, 9, 1)
+            ]
+        );
+        assert_eq!(report.covered_sections(), 8);
+        assert_eq!(report.max_assignments(), 8);
+        assert!(report.gaps().is_empty());
+        assert_eq!(
+            report.covered_by_more_than(6),
+            Interval::new(4, 6).unwrap().into()
+        );
+        assert!(report.covered_by_more_than(8).is_empty());
+    }
+
+    #[test]
+    fn test_gaps() {
+        let report = CoverageReport::new(&ranges(&[(1, 3), (10, 12), (2, 4), (20, 1_000_000_000)]));
+        assert_eq!(report.covered_sections(), 4 + 3 + 999_999_981);
+        assert_eq!(
+            report.gaps(),
+            ranges(&[(5, 9), (13, 19)]).into_iter().collect()
+        );
+        assert_eq!(
+            report.covered_by_more_than(1),
+            Interval::new(2, 3).unwrap().into()
+        );
+    }
+
+    #[test]
+    fn test_render_heatmap() {
+        assert_eq!(
+            example_report().render_heatmap(5, 10),
+            "2 |+*%%@|\n7 |#+:|\n1 section(s) per cell, '@' is 8 elves\n"
+        );
+
+        // Each cell shows the most assigned of its 4 sections
+        let report = CoverageReport::new(&ranges(&[(1, 4), (3, 5), (11, 16)]));
+        assert_eq!(
+            report.render_heatmap(2, 2),
+            " 1 |@+|\n 9 |++|\n4 section(s) per cell, '@' is 2 elves\n"
+        );
+        assert_eq!(CoverageReport::new(&[]).render_heatmap(10, 10), "");
+        assert_eq!(example_report().render_heatmap(0, 10), "");
+        assert_eq!(example_report().render_heatmap(10, 0), "");
+    }
+
+    #[test]
+    fn test_full_domain() {
+        let report = CoverageReport::new(&ranges(&[(0, u64::MAX), (0, u64::MAX)]));
+        assert_eq!(report.covered_sections(), 1 << 64);
+        assert_eq!(
+            report.render_heatmap(4, 1),
+            format!(
+                "{:>20} |@@@@|\n{} section(s) per cell, '@' is 2 elves\n",
+                0,
+                1u64 << 62
+            )
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use interval::{Interval, IntervalSet};
+
+// Analysis of the assignments of a group of elves, elves are identified
+// by their 0-based position on the input line
+#[derive(Debug, PartialEq)]
+pub struct GroupAnalysis {
+    // Elves whose sections are all covered by the rest of the group
+    pub redundant_elves: Vec<usize>,
+    // Number of sections assigned to more than one elf
+    pub overlap: u128,
+    // Sections between the lowest and highest assigned ones that nobody cleans
+    pub uncovered: IntervalSet,
+    // Smallest set of elves that still cleans every section of the group
+    pub minimal_cover: Vec<usize>,
+}
+
+pub fn analyze_group(ranges: &[Interval]) -> GroupAnalysis {
+    let covered: IntervalSet = ranges.iter().copied().collect();
+
+    let redundant_elves = (0..ranges.len())
+        .filter(|&elf| {
+            let others: IntervalSet = ranges
+                .iter()
+                .enumerate()
+                .filter(|&(other, _)| other != elf)
+                .map(|(_, range)| *range)
+                .collect();
+            others.contains(&ranges[elf])
+        })
+        .collect();
+
+    let mut seen = IntervalSet::new();
+    let mut overlap = IntervalSet::new();
+    for range in ranges {
+        let range = IntervalSet::from(*range);
+        overlap = overlap.union(&seen.intersection(&range));
+        seen = seen.union(&range);
+    }
+
+    let uncovered = match (covered.intervals().first(), covered.intervals().last()) {
+        (Some(first), Some(last)) => {
+            IntervalSet::from(Interval::new(first.start, last.end).unwrap()).difference(&covered)
+        }
+        _ => IntervalSet::new(),
+    };
+
+    GroupAnalysis {
+        redundant_elves,
+        overlap: overlap.len(),
+        uncovered,
+        minimal_cover: minimal_cover(ranges),
+    }
+}
+
+// Greedy interval cover: while walking the sections from left to right,
+// always pick the elf that starts before the first uncovered section and
+// reaches the furthest. This is optimal for intervals.
+fn minimal_cover(ranges: &[Interval]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..ranges.len()).collect();
+    order.sort_by_key(|&elf| ranges[elf].start);
+
+    let mut cover = Vec::new();
+    let mut covered_end: Option<u64> = None;
+    let mut i = 0;
+    while i < order.len() {
+        // First section that still needs an elf, either right after the covered
+        // sections or the beginning of the next disjoint block of sections
+        let next_section = match covered_end {
+            Some(end) if ranges[order[i]].start <= end.saturating_add(1) => end.saturating_add(1),
+            _ => ranges[order[i]].start,
+        };
+
+        let mut best = order[i];
+        while i < order.len() && ranges[order[i]].start <= next_section {
+            if ranges[order[i]].end > ranges[best].end {
+                best = order[i];
+            }
+            i += 1;
+        }
+
+        if covered_end.is_none_or(|end| ranges[best].end > end) {
+            cover.push(best);
+            covered_end = Some(ranges[best].end);
+        }
+    }
+    cover.sort();
+    cover
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(ranges: &[(u64, u64)]) -> Vec<Interval> {
+        ranges
+            .iter()
+            .map(|&(start, end)| Interval::new(start, end).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_analyze_pair() {
+        let analysis = analyze_group(&ranges(&[(2, 8), (3, 7)]));
+        assert_eq!(analysis.redundant_elves, vec![1]);
+        assert_eq!(analysis.overlap, 5);
+        assert!(analysis.uncovered.is_empty());
+        assert_eq!(analysis.minimal_cover, vec![0]);
+
+        let analysis = analyze_group(&ranges(&[(2, 3), (6, 8)]));
+        assert_eq!(analysis.redundant_elves, Vec::<usize>::new());
+        assert_eq!(analysis.overlap, 0);
+        assert_eq!(analysis.uncovered, Interval::new(4, 5).unwrap().into());
+        assert_eq!(analysis.minimal_cover, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_analyze_group() {
+        // Elf 1 is covered by 0 and 2 together, 3 is on its own
+        let analysis = analyze_group(&ranges(&[(1, 5), (4, 8), (6, 10), (20, 22), (2, 3)]));
+        assert_eq!(analysis.redundant_elves, vec![1, 4]);
+        assert_eq!(analysis.overlap, 2 + 2 + 3);
+        assert_eq!(analysis.uncovered, Interval::new(11, 19).unwrap().into());
+        assert_eq!(analysis.minimal_cover, vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn test_analyze_full_domain() {
+        let analysis = analyze_group(&ranges(&[(0, u64::MAX), (5, u64::MAX)]));
+        assert_eq!(analysis.overlap, (1 << 64) - 5);
+        assert_eq!(analysis.minimal_cover, vec![0]);
+    }
+
+    #[test]
+    fn test_minimal_cover_identical_ranges() {
+        // Both elves are redundant, but one of them must stay
+        let analysis = analyze_group(&ranges(&[(3, 6), (3, 6)]));
+        assert_eq!(analysis.redundant_elves, vec![0, 1]);
+        assert_eq!(analysis.minimal_cover.len(), 1);
+    }
+
+    #[test]
+    fn test_minimal_cover_prefers_longest_reach() {
+        let cover = minimal_cover(&ranges(&[(1, 3), (1, 6), (2, 4), (5, 9), (7, 12), (4, 10)]));
+        assert_eq!(cover, vec![1, 4]);
+        assert_eq!(minimal_cover(&[]), Vec::<usize>::new());
+    }
+}
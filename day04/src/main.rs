@@ -1,4 +1,9 @@
-use itertools::Itertools;
+mod coverage;
+mod group;
+
+use coverage::CoverageReport;
+use group::analyze_group;
+use interval::Interval;
 use std::fs;
 
 fn main() {
@@ -6,47 +11,81 @@ fn main() {
 
     println!("part1 {}", part1(&data));
     println!("part2 {}", part2(&data));
+
+    let analyses: Vec<_> = get_ranges_per_pair_per_elf(&data)
+        .iter()
+        .map(|ranges| analyze_group(ranges))
+        .collect();
+    println!(
+        "redundant elves {}",
+        analyses
+            .iter()
+            .map(|a| a.redundant_elves.len())
+            .sum::<usize>()
+    );
+    println!(
+        "overlapping sections {}",
+        analyses.iter().map(|a| a.overlap).sum::<u128>()
+    );
+    println!(
+        "uncovered sections {}",
+        analyses.iter().map(|a| a.uncovered.len()).sum::<u128>()
+    );
+    println!(
+        "elves needed {}",
+        analyses
+            .iter()
+            .map(|a| a.minimal_cover.len())
+            .sum::<usize>()
+    );
+    let ranges = get_ranges_per_pair_per_elf(&data);
+    let report = CoverageReport::new(ranges.iter().flatten());
+    println!("covered sections {}", report.covered_sections());
+    println!(
+        "sections with more than 2 elves {}",
+        report.covered_by_more_than(2).len()
+    );
+    println!("gaps {:?}", report.gaps().intervals());
+    print!("{}", report.render_heatmap(50, 10));
 }
 
-type IdsRange = Vec<Vec<Vec<i32>>>;
+type IdsRange = Vec<Vec<Interval>>;
 
 fn get_ranges_per_pair_per_elf(data: &str) -> IdsRange {
     data.lines()
-        .map(|l| l.split(","))
+        .map(|l| l.split(','))
         .map(|split| {
             split
                 .into_iter()
-                .map(|s| s.split("-").filter_map(|n| n.parse::<i32>().ok()))
-                .filter_map(|mut n| {
-                    let first = n.next()?;
-                    let second = n.next()?;
-                    Some((first..second + 1).collect())
-                })
+                .map(|s| s.split('-').filter_map(|n| n.parse::<u64>().ok()))
+                .filter_map(|mut n| Interval::new(n.next()?, n.next()?))
                 .collect()
         })
         .collect()
 }
 
-fn get_duplicates_per_elf_pair(ranges: &IdsRange) -> Vec<usize> {
-    ranges
-        .iter()
-        .map(|ids_range| ids_range.concat().into_iter().duplicates().count())
-        .collect()
-}
-
+// One elf of the pair has a range that contains the ranges of all the others
 fn part1(data: &str) -> usize {
-    let ranges = get_ranges_per_pair_per_elf(data);
-    ranges
+    get_ranges_per_pair_per_elf(data)
         .iter()
-        .zip(get_duplicates_per_elf_pair(&ranges).iter())
-        .filter(|(range, &duplicate_count)| range.iter().any(|r| r.len() == duplicate_count))
+        .filter(|ranges| {
+            ranges
+                .iter()
+                .any(|range| ranges.iter().all(|other| range.contains(other)))
+        })
         .count()
 }
 
+// At least two elves of the pair have overlapping ranges
 fn part2(data: &str) -> usize {
-    get_duplicates_per_elf_pair(&get_ranges_per_pair_per_elf(data))
+    get_ranges_per_pair_per_elf(data)
         .iter()
-        .filter(|&&d| d > 0)
+        .filter(|ranges| {
+            ranges
+                .iter()
+                .enumerate()
+                .any(|(i, range)| ranges[i + 1..].iter().any(|other| range.overlaps(other)))
+        })
         .count()
 }
 
@@ -61,21 +100,46 @@ mod tests {
 6-6,4-6
 2-6,4-8";
 
+    fn interval(start: u64, end: u64) -> Interval {
+        Interval::new(start, end).unwrap()
+    }
+
     #[test]
     fn test_get_ranges_per_pair_per_elf() {
         assert_eq!(
             get_ranges_per_pair_per_elf(EXAMPLE),
             vec![
-                vec![vec![2, 3, 4], vec![6, 7, 8]],
-                vec![vec![2, 3], vec![4, 5]],
-                vec![vec![5, 6, 7], vec![7, 8, 9]],
-                vec![vec![2, 3, 4, 5, 6, 7, 8], vec![3, 4, 5, 6, 7]],
-                vec![vec![6], vec![4, 5, 6]],
-                vec![vec![2, 3, 4, 5, 6], vec![4, 5, 6, 7, 8]],
+                vec![interval(2, 4), interval(6, 8)],
+                vec![interval(2, 3), interval(4, 5)],
+                vec![interval(5, 7), interval(7, 9)],
+                vec![interval(2, 8), interval(3, 7)],
+                vec![interval(6, 6), interval(4, 6)],
+                vec![interval(2, 6), interval(4, 8)],
             ]
         )
     }
 
+    #[test]
+    fn test_groups_of_any_size() {
+        let data = "1-5,4-8,6-10\n1-2,3-4,5-6,7-8\n3-3";
+        assert_eq!(
+            get_ranges_per_pair_per_elf(data)
+                .iter()
+                .map(|ranges| ranges.len())
+                .collect::<Vec<_>>(),
+            vec![3, 4, 1]
+        );
+        assert_eq!(part1(data), 1);
+        assert_eq!(part2(data), 1);
+    }
+
+    #[test]
+    fn test_large_ranges() {
+        let data = "1-1000000000,2-999999999\n1-1000000000,1000000000-2000000000";
+        assert_eq!(part1(data), 1);
+        assert_eq!(part2(data), 2);
+    }
+
     #[test]
     fn test_part1() {
         assert_eq!(part1(EXAMPLE), 2)
@@ -0,0 +1,138 @@
+use crate::CrateStack;
+
+// A crane model moving crates between two stacks
+pub trait CrateMover {
+    // Move the `count` top crates of `from` onto `to` and return the cost of the move.
+    // `count` is never greater than the height of `from`.
+    fn move_crates(&self, from: &mut CrateStack, to: &mut CrateStack, count: usize) -> u64;
+}
+
+// Moves crates one at a time, one lift per crate
+pub struct CrateMover9000;
+
+impl CrateMover for CrateMover9000 {
+    fn move_crates(&self, from: &mut CrateStack, to: &mut CrateStack, count: usize) -> u64 {
+        let lifted = from.split_off(from.len() - count);
+        to.extend(lifted.into_iter().rev());
+        count as u64
+    }
+}
+
+// Moves all the crates at once, keeping their order, in a single lift
+pub struct CrateMover9001;
+
+impl CrateMover for CrateMover9001 {
+    fn move_crates(&self, from: &mut CrateStack, to: &mut CrateStack, count: usize) -> u64 {
+        let lifted = from.split_off(from.len() - count);
+        to.extend(lifted);
+        1
+    }
+}
+
+// Like the 9001 but it can't lift more than `capacity` crates,
+// large moves are split in several lifts starting from the top
+pub struct CapacityLimitedMover {
+    pub capacity: usize,
+}
+
+impl CrateMover for CapacityLimitedMover {
+    fn move_crates(&self, from: &mut CrateStack, to: &mut CrateStack, count: usize) -> u64 {
+        assert!(self.capacity > 0);
+        let mut lifts = 0;
+        let mut remaining = count;
+        while remaining > 0 {
+            let lifted = from.split_off(from.len() - remaining.min(self.capacity));
+            remaining -= lifted.len();
+            to.extend(lifted);
+            lifts += 1;
+        }
+        lifts
+    }
+}
+
+// Lifts all the crates at once but the upper half of them flips over
+pub struct HalfFlipMover;
+
+impl CrateMover for HalfFlipMover {
+    fn move_crates(&self, from: &mut CrateStack, to: &mut CrateStack, count: usize) -> u64 {
+        let mut lifted = from.split_off(from.len() - count);
+        lifted[count - count / 2..].reverse();
+        to.extend(lifted);
+        1
+    }
+}
+
+// Moves crates like the 9001 and burns fuel for each crate moved
+pub struct FuelPoweredMover {
+    pub fuel_per_crate: u64,
+}
+
+impl CrateMover for FuelPoweredMover {
+    fn move_crates(&self, from: &mut CrateStack, to: &mut CrateStack, count: usize) -> u64 {
+        CrateMover9001.move_crates(from, to, count);
+        count as u64 * self.fuel_per_crate
+    }
+}
+
+// Select a crate mover from its name:
+// "9000", "9001", "capacity=<k>", "half-flip" or "fuel=<per crate>"
+pub fn crate_mover_from_name(name: &str) -> Option<Box<dyn CrateMover>> {
+    match name.split_once('=') {
+        None => match name {
+            "9000" => Some(Box::new(CrateMover9000)),
+            "9001" => Some(Box::new(CrateMover9001)),
+            "half-flip" => Some(Box::new(HalfFlipMover)),
+            _ => None,
+        },
+        Some(("capacity", capacity)) => match capacity.parse() {
+            Ok(capacity) if capacity > 0 => Some(Box::new(CapacityLimitedMover { capacity })),
+            _ => None,
+        },
+        Some(("fuel", fuel_per_crate)) => Some(Box::new(FuelPoweredMover {
+            fuel_per_crate: fuel_per_crate.parse().ok()?,
+        })),
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::stack_from;
+
+    fn move_crates(crate_mover: &dyn CrateMover, count: usize) -> (CrateStack, u64) {
+        let mut from = stack_from("ABCDE");
+        let mut to = stack_from("Z");
+        let cost = crate_mover.move_crates(&mut from, &mut to, count);
+        assert_eq!(from.len() + to.len(), 6);
+        (to, cost)
+    }
+
+    #[test]
+    fn test_crate_movers() {
+        assert_eq!(move_crates(&CrateMover9000, 3), (stack_from("ZEDC"), 3));
+        assert_eq!(move_crates(&CrateMover9001, 3), (stack_from("ZCDE"), 1));
+        assert_eq!(
+            move_crates(&CapacityLimitedMover { capacity: 2 }, 5),
+            (stack_from("ZDEBCA"), 3)
+        );
+        assert_eq!(move_crates(&HalfFlipMover, 5), (stack_from("ZABCED"), 1));
+        assert_eq!(
+            move_crates(&FuelPoweredMover { fuel_per_crate: 7 }, 2),
+            (stack_from("ZDE"), 14)
+        );
+        assert_eq!(move_crates(&CrateMover9000, 0), (stack_from("Z"), 0));
+    }
+
+    #[test]
+    fn test_crate_mover_from_name() {
+        for name in ["9000", "9001", "capacity=3", "half-flip", "fuel=2"] {
+            assert!(crate_mover_from_name(name).is_some(), "{}", name);
+        }
+        for name in ["9002", "capacity=0", "capacity=", "fuel=x", "speed=2"] {
+            assert!(crate_mover_from_name(name).is_none(), "{}", name);
+        }
+        let (to, cost) = move_crates(crate_mover_from_name("capacity=3").unwrap().as_ref(), 4);
+        assert_eq!((to, cost), (stack_from("ZCDEB"), 2));
+    }
+}
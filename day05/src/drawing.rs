@@ -0,0 +1,378 @@
+use crate::{Crate, Op, Stacks};
+
+#[derive(Debug, PartialEq)]
+pub enum DrawingParseError {
+    // The input has no drawing before the blank line
+    MissingDrawing,
+    // The last line of the drawing isn't the numbered footer
+    MissingLabels { line: usize },
+    // A label isn't the next stack number
+    InvalidLabel { line: usize, column: usize },
+    // Something else than a crate or spaces is found, or a crate
+    // isn't above exactly one stack label
+    MisalignedCrate { line: usize, column: usize },
+    // A crate without label "[]"
+    EmptyCrate { line: usize, column: usize },
+    // A crate is drawn right of the last labeled stack
+    UnlabeledStack { line: usize, stack: usize },
+    // A crate is drawn above an empty slot
+    FloatingCrate { line: usize, stack: usize },
+}
+
+// Parse the footer " 1   2   3 " and return the column of each stack label
+fn parse_labels(line_number: usize, line: &str) -> Result<Vec<usize>, DrawingParseError> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut columns = Vec::new();
+    let mut column = 0;
+    while column < chars.len() {
+        if chars[column] == ' ' {
+            column += 1;
+            continue;
+        }
+        let len = chars[column..].iter().take_while(|c| **c != ' ').count();
+        let label: String = chars[column..column + len].iter().collect();
+        if label.parse::<usize>().ok() != Some(columns.len() + 1) {
+            return Err(DrawingParseError::InvalidLabel {
+                line: line_number,
+                column: column + 1,
+            });
+        }
+        columns.push(column);
+        column += len;
+    }
+    if columns.is_empty() {
+        return Err(DrawingParseError::MissingLabels { line: line_number });
+    }
+    Ok(columns)
+}
+
+// Parse a line of crates, with one entry per stack that has a crate.
+// A crate belongs to the stack whose label is under its brackets.
+fn parse_crate_line(
+    line_number: usize,
+    line: &str,
+    label_columns: &[usize],
+) -> Result<Vec<(usize, Crate)>, DrawingParseError> {
+    let chars: Vec<char> = line.chars().collect();
+    let misaligned = |column: usize| DrawingParseError::MisalignedCrate {
+        line: line_number,
+        column: column + 1,
+    };
+
+    let mut crates = Vec::new();
+    let mut column = 0;
+    while column < chars.len() {
+        match chars[column] {
+            ' ' => column += 1,
+            '[' => {
+                let label_len = chars[column + 1..]
+                    .iter()
+                    .take_while(|c| !matches!(c, '[' | ']') && !c.is_whitespace())
+                    .count();
+                let close = column + 1 + label_len;
+                if chars.get(close) != Some(&']') {
+                    return Err(misaligned(column));
+                }
+                if label_len == 0 {
+                    return Err(DrawingParseError::EmptyCrate {
+                        line: line_number,
+                        column: column + 1,
+                    });
+                }
+
+                let stacks: Vec<usize> = label_columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, label)| (column..=close).contains(label))
+                    .map(|(stack, _)| stack)
+                    .collect();
+                match stacks[..] {
+                    [stack] => {
+                        let label: String = chars[column + 1..close].iter().collect();
+                        crates.push((stack, Crate::from(label.as_str())));
+                    }
+                    [] if label_columns.last().is_some_and(|last| column > *last) => {
+                        return Err(DrawingParseError::UnlabeledStack {
+                            line: line_number,
+                            stack: label_columns.len() + 1,
+                        });
+                    }
+                    _ => return Err(misaligned(column)),
+                }
+                column = close + 1;
+            }
+            _ => return Err(misaligned(column)),
+        }
+    }
+    Ok(crates)
+}
+
+// Parse the crate drawing, including its numbered footer
+pub fn parse_stacks(drawing: &str) -> Result<Stacks, DrawingParseError> {
+    let lines: Vec<&str> = drawing.lines().collect();
+    let (footer, crate_lines) = lines
+        .split_last()
+        .ok_or(DrawingParseError::MissingDrawing)?;
+    if footer.contains('[') {
+        return Err(DrawingParseError::MissingLabels { line: lines.len() });
+    }
+    let label_columns = parse_labels(lines.len(), footer)?;
+
+    // Stack crates from the bottom line up
+    let mut stacks: Stacks = vec![Vec::new(); label_columns.len()];
+    for (idx, line) in crate_lines.iter().enumerate().rev() {
+        let level = crate_lines.len() - 1 - idx;
+        for (stack, name) in parse_crate_line(idx + 1, line, &label_columns)? {
+            if stacks[stack].len() != level {
+                return Err(DrawingParseError::FloatingCrate {
+                    line: idx + 1,
+                    stack: stack + 1,
+                });
+            }
+            stacks[stack].push(name);
+        }
+    }
+    Ok(stacks)
+}
+
+// Split the puzzle input into the starting stacks and the move lines,
+// with the number of input lines before the moves
+pub fn parse_puzzle_input(input: &str) -> Result<(Stacks, &str, usize), DrawingParseError> {
+    let data = input.trim_start_matches('\n');
+    let (drawing, moves) = match data.split_once("\n\n") {
+        Some((drawing, moves)) => (drawing, moves),
+        None if data.starts_with("move") => ("", data),
+        None => (data, ""),
+    };
+    if drawing.trim().is_empty() {
+        return Err(DrawingParseError::MissingDrawing);
+    }
+    let offset = input[..input.len() - moves.len()].matches('\n').count();
+    Ok((parse_stacks(drawing)?, moves, offset))
+}
+
+#[derive(Debug, Default)]
+pub struct RenderOptions<'a> {
+    // Show at most this many rows of crates, taller stacks are cut
+    // at the bottom and end with "..."
+    pub max_height: Option<usize>,
+    // Draw the crates moved by this operation as "(X)" instead of "[X]"
+    pub moved_by: Option<&'a Op>,
+}
+
+// Draw the stacks like the puzzle input does, every column is as wide
+// as the longest crate label. Without options the output can be read
+// back by `parse_stacks`.
+pub fn render_stacks(stacks: &Stacks, options: &RenderOptions) -> String {
+    let tallest = stacks.iter().map(|stack| stack.len()).max().unwrap_or(0);
+    let height = options.max_height.map_or(tallest, |max| max.min(tallest));
+    let longest = stacks.iter().flatten().map(|name| name.0.chars().count());
+    let width = (longest.max().unwrap_or(1) + 2).max(stacks.len().to_string().len() + 1);
+
+    let cell = |stack_idx: usize, level: usize| -> String {
+        let stack = &stacks[stack_idx];
+        // Index of the crate drawn at `level`, the bottom crates
+        // of a stack taller than the drawing are replaced by "..."
+        let idx = if stack.len() > height {
+            if level == 0 {
+                return format!("{:<width$}", "...");
+            }
+            stack.len() - height + level
+        } else {
+            level
+        };
+        match stack.get(idx) {
+            Some(name) => {
+                let moved = options
+                    .moved_by
+                    .is_some_and(|op| op.to == stack_idx + 1 && idx + op.count >= stack.len());
+                let name = if moved {
+                    format!("({})", name)
+                } else {
+                    format!("[{}]", name)
+                };
+                format!("{:<width$}", name)
+            }
+            None => " ".repeat(width),
+        }
+    };
+
+    let mut drawing = String::new();
+    for level in (0..height).rev() {
+        let row: Vec<String> = (0..stacks.len()).map(|stack| cell(stack, level)).collect();
+        drawing += &row.join(" ");
+        drawing.push('\n');
+    }
+    let labels: Vec<String> = (1..=stacks.len())
+        .map(|label| format!(" {:<1$}", label, width - 1))
+        .collect();
+    drawing += &labels.join(" ");
+    drawing.push('\n');
+    drawing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{stack_from, stacks_from};
+
+    static EXAMPLE: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2
+";
+
+    #[test]
+    fn test_parse_puzzle_input() {
+        let (stacks, moves, offset) = parse_puzzle_input(EXAMPLE).unwrap();
+        assert_eq!(stacks, stacks_from(&["ZN", "MCD", "P"]));
+        assert!(moves.starts_with("move 1 from 2 to 1\n"));
+        assert_eq!(moves.lines().count(), 4);
+        assert_eq!(EXAMPLE.lines().nth(offset), Some("move 1 from 2 to 1"));
+        let (_, _, offset) = parse_puzzle_input(&format!("\n\n{}", EXAMPLE)).unwrap();
+        assert_eq!(offset, 7);
+    }
+
+    #[test]
+    fn test_parse_ragged_stacks() {
+        // Trailing spaces trimmed, an empty stack and more than 9 stacks
+        let drawing = "[A]
+[B]             [C]
+[D]     [E]     [F]                             [G]
+ 1   2   3   4   5   6   7   8   9   10  11  12  13";
+        let stacks = parse_stacks(drawing).unwrap();
+        assert_eq!(stacks.len(), 13);
+        assert_eq!(stacks[0], stack_from("DBA"));
+        assert_eq!(stacks[1], vec![]);
+        assert_eq!(stacks[2], stack_from("E"));
+        assert_eq!(stacks[4], stack_from("FC"));
+        assert_eq!(stacks[12], stack_from("G"));
+
+        assert_eq!(parse_stacks(" 1   2 ").unwrap(), vec![vec![], vec![]]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_puzzle_input("move 1 from 2 to 1").unwrap_err(),
+            DrawingParseError::MissingDrawing
+        );
+        assert_eq!(
+            parse_stacks("[A] [B]\n[C] [D]").unwrap_err(),
+            DrawingParseError::MissingLabels { line: 2 }
+        );
+        assert_eq!(
+            parse_stacks("   [A]\n 1     2 ").unwrap_err(),
+            DrawingParseError::MisalignedCrate { line: 1, column: 4 }
+        );
+        assert_eq!(
+            parse_stacks("[ABCDE]\n 1   2 ").unwrap_err(),
+            DrawingParseError::MisalignedCrate { line: 1, column: 1 }
+        );
+        assert_eq!(
+            parse_stacks("[A] []\n 1   2 ").unwrap_err(),
+            DrawingParseError::EmptyCrate { line: 1, column: 5 }
+        );
+        assert_eq!(
+            parse_stacks("[A] [B\n 1   2 ").unwrap_err(),
+            DrawingParseError::MisalignedCrate { line: 1, column: 5 }
+        );
+        assert_eq!(
+            parse_stacks("[A] [B] [C]\n 1   2 ").unwrap_err(),
+            DrawingParseError::UnlabeledStack { line: 1, stack: 3 }
+        );
+        assert_eq!(
+            parse_stacks("[A] [B]\n 1   3 ").unwrap_err(),
+            DrawingParseError::InvalidLabel { line: 2, column: 6 }
+        );
+        assert_eq!(
+            parse_stacks("[A] [B]\n 1   1 ").unwrap_err(),
+            DrawingParseError::InvalidLabel { line: 2, column: 6 }
+        );
+        assert_eq!(
+            parse_stacks("[A] [B]\n    [C]\n 1   2 ").unwrap_err(),
+            DrawingParseError::FloatingCrate { line: 1, stack: 1 }
+        );
+    }
+
+    #[test]
+    fn test_render_round_trip() {
+        let (stacks, _, _) = parse_puzzle_input(EXAMPLE).unwrap();
+        let drawing = render_stacks(&stacks, &RenderOptions::default());
+        assert_eq!(
+            drawing,
+            EXAMPLE.split("\n\n").next().unwrap().to_string() + "\n"
+        );
+        assert_eq!(parse_stacks(&drawing).unwrap(), stacks);
+
+        let stacks = stacks_from(&["A", "", "BCD", "", "", "", "", "", "", "EF", "G"]);
+        let drawing = render_stacks(&stacks, &RenderOptions::default());
+        assert_eq!(
+            drawing,
+            "        [D]                                \n\
+             \x20       [C]                         [F]    \n\
+             [A]     [B]                         [E] [G]\n\
+             \x201   2   3   4   5   6   7   8   9   10  11\n"
+        );
+        assert_eq!(parse_stacks(&drawing).unwrap(), stacks);
+    }
+
+    #[test]
+    fn test_render_truncated() {
+        let stacks = stacks_from(&["ABCDE", "F", "GH"]);
+        let options = RenderOptions {
+            max_height: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_stacks(&stacks, &options),
+            "[E]        \n[D]     [H]\n... [F] [G]\n 1   2   3 \n"
+        );
+    }
+
+    #[test]
+    fn test_render_moved_crates() {
+        let stacks = stacks_from(&["ZND", "MC", "P"]);
+        let op = Op {
+            count: 2,
+            from: 2,
+            to: 1,
+        };
+        let options = RenderOptions {
+            moved_by: Some(&op),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_stacks(&stacks, &options),
+            "(D)        \n(N) [C]    \n[Z] [M] [P]\n 1   2   3 \n"
+        );
+    }
+
+    #[test]
+    fn test_multi_character_labels() {
+        let drawing = "     [AB12]\n[Z]  [C]    [foo]\n 1     2      3";
+        let stacks = parse_stacks(drawing).unwrap();
+        assert_eq!(
+            stacks,
+            vec![
+                vec![Crate::from("Z")],
+                vec![Crate::from("C"), Crate::from("AB12")],
+                vec![Crate::from("foo")],
+            ]
+        );
+
+        let drawing = render_stacks(&stacks, &RenderOptions::default());
+        assert_eq!(
+            drawing,
+            "       [AB12]       \n\
+             [Z]    [C]    [foo] \n\
+             \x201      2      3    \n"
+        );
+        assert_eq!(parse_stacks(&drawing).unwrap(), stacks);
+    }
+}
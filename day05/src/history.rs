@@ -0,0 +1,298 @@
+use crate::crane::CrateMover;
+use crate::{apply_op, ApplyOpError, Crate, Op, Stacks};
+
+// An operation that was applied, with the crates it moved so it can be undone
+// whatever crate mover was used
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedOp {
+    pub op: Op,
+    pub cost: u64,
+    // Crates taken from the top of `from`, bottom first
+    lifted: Vec<Crate>,
+    // Crates put on the top of `to`, bottom first
+    placed: Vec<Crate>,
+}
+
+impl AppliedOp {
+    fn redo(&self, stacks: &mut Stacks) {
+        let from = &mut stacks[self.op.from - 1];
+        from.truncate(from.len() - self.lifted.len());
+        stacks[self.op.to - 1].extend(self.placed.iter().cloned());
+    }
+
+    fn undo(&self, stacks: &mut Stacks) {
+        let to = &mut stacks[self.op.to - 1];
+        to.truncate(to.len() - self.placed.len());
+        stacks[self.op.from - 1].extend(self.lifted.iter().cloned());
+    }
+}
+
+// How a stack changed between two steps
+#[derive(Debug, PartialEq)]
+pub struct StackDiff {
+    // 1-based index of the stack
+    pub stack: usize,
+    // Crates that were on top of the stack and are gone, bottom first
+    pub removed: Vec<Crate>,
+    // Crates that are now on top of the stack, bottom first
+    pub added: Vec<Crate>,
+}
+
+// Log of the operations applied to some stacks.
+// Step 0 is the initial stacks and step N the stacks after the N first operations.
+pub struct OpHistory {
+    initial: Stacks,
+    current: Stacks,
+    applied: Vec<AppliedOp>,
+    step: usize,
+}
+
+impl OpHistory {
+    pub fn new(stacks: Stacks) -> OpHistory {
+        OpHistory {
+            initial: stacks.clone(),
+            current: stacks,
+            applied: Vec::new(),
+            step: 0,
+        }
+    }
+
+    // Number of operations recorded
+    pub fn len(&self) -> usize {
+        self.applied.len()
+    }
+
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    // Stacks at the current step
+    pub fn stacks(&self) -> &Stacks {
+        &self.current
+    }
+
+    pub fn applied_ops(&self) -> &[AppliedOp] {
+        &self.applied
+    }
+
+    // Apply `op` at the current step, operations that were undone are forgotten.
+    // Invalid operations leave the history untouched.
+    pub fn apply(&mut self, op: Op, crate_mover: &dyn CrateMover) -> Result<u64, ApplyOpError> {
+        // Crates about to be lifted, read before the move as crate movers
+        // may reorder them. An invalid operation fails in `apply_op`.
+        let lifted = op
+            .from
+            .checked_sub(1)
+            .and_then(|idx| self.current.get(idx))
+            .and_then(|from| Some(from[from.len().checked_sub(op.count)?..].to_vec()))
+            .unwrap_or_default();
+        let cost = apply_op(&op, &mut self.current, crate_mover)?;
+
+        let (lifted, placed) = if op.from == op.to {
+            (Vec::new(), Vec::new())
+        } else {
+            let to = &self.current[op.to - 1];
+            (lifted, to[to.len() - op.count..].to_vec())
+        };
+
+        self.applied.truncate(self.step);
+        self.applied.push(AppliedOp {
+            op,
+            cost,
+            lifted,
+            placed,
+        });
+        self.step += 1;
+        Ok(cost)
+    }
+
+    // Step backward, return the operation that was undone
+    pub fn undo(&mut self) -> Option<&Op> {
+        if self.step == 0 {
+            return None;
+        }
+        self.step -= 1;
+        let applied = &self.applied[self.step];
+        applied.undo(&mut self.current);
+        Some(&applied.op)
+    }
+
+    // Step forward, return the operation that was applied again
+    pub fn redo(&mut self) -> Option<&Op> {
+        let applied = self.applied.get(self.step)?;
+        applied.redo(&mut self.current);
+        self.step += 1;
+        Some(&applied.op)
+    }
+
+    // Move the current step to `step`, return false if it doesn't exist
+    pub fn seek(&mut self, step: usize) -> bool {
+        if step > self.len() {
+            return false;
+        }
+        while self.step > step {
+            self.undo();
+        }
+        while self.step < step {
+            self.redo();
+        }
+        true
+    }
+
+    // Rebuild the stacks at any step without moving the current one
+    pub fn stacks_at(&self, step: usize) -> Option<Stacks> {
+        let mut stacks = self.initial.clone();
+        for applied in self.applied.get(..step)? {
+            applied.redo(&mut stacks);
+        }
+        Some(stacks)
+    }
+
+    // Changes of each stack between steps `from` and `to`, unchanged stacks are omitted
+    pub fn diff(&self, from: usize, to: usize) -> Option<Vec<StackDiff>> {
+        let before = self.stacks_at(from)?;
+        let after = self.stacks_at(to)?;
+        Some(
+            before
+                .iter()
+                .zip(after.iter())
+                .enumerate()
+                .filter(|(_, (before, after))| before != after)
+                .map(|(idx, (before, after))| {
+                    let common = before
+                        .iter()
+                        .zip(after.iter())
+                        .take_while(|(b, a)| b == a)
+                        .count();
+                    StackDiff {
+                        stack: idx + 1,
+                        removed: before[common..].to_vec(),
+                        added: after[common..].to_vec(),
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crane::{CrateMover9000, CrateMover9001, HalfFlipMover};
+    use crate::get_ops;
+    use crate::tests::{_get_tests_stacks, stack_from, stacks_from, _TEST_DATA};
+
+    fn record(crate_mover: &dyn CrateMover) -> OpHistory {
+        let mut history = OpHistory::new(_get_tests_stacks());
+        for op in get_ops(_TEST_DATA).unwrap() {
+            history.apply(op, crate_mover).unwrap();
+        }
+        history
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        for crate_mover in [
+            &CrateMover9000 as &dyn CrateMover,
+            &CrateMover9001,
+            &HalfFlipMover,
+        ] {
+            let mut history = record(crate_mover);
+            let last = history.stacks().clone();
+            assert_eq!(history.len(), 4);
+
+            while history.undo().is_some() {}
+            assert_eq!(history.step(), 0);
+            assert_eq!(history.stacks(), &_get_tests_stacks());
+
+            while history.redo().is_some() {}
+            assert_eq!(history.step(), 4);
+            assert_eq!(history.stacks(), &last);
+        }
+    }
+
+    #[test]
+    fn test_stacks_at() {
+        let mut history = record(&CrateMover9000);
+        assert_eq!(history.stacks_at(0), Some(_get_tests_stacks()));
+        assert_eq!(history.stacks_at(2), Some(stacks_from(&["", "MC", "PDNZ"])));
+        assert_eq!(history.stacks_at(5), None);
+
+        assert!(history.seek(2));
+        assert_eq!(history.stacks(), &history.stacks_at(2).unwrap());
+        assert_eq!(
+            history.undo(),
+            Some(&Op {
+                count: 3,
+                from: 1,
+                to: 3
+            })
+        );
+        assert!(!history.seek(5));
+        assert_eq!(history.step(), 1);
+    }
+
+    #[test]
+    fn test_apply_forgets_undone_ops() {
+        let mut history = record(&CrateMover9001);
+        history.seek(1);
+        assert_eq!(
+            history.apply(
+                Op {
+                    count: 1,
+                    from: 3,
+                    to: 2
+                },
+                &CrateMover9001
+            ),
+            Ok(1)
+        );
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.stacks(), &stacks_from(&["ZND", "MCP", ""]));
+
+        // Invalid ops are not recorded
+        assert_eq!(
+            history.apply(
+                Op {
+                    count: 1,
+                    from: 3,
+                    to: 2
+                },
+                &CrateMover9001
+            ),
+            Err(ApplyOpError::InvalidCount(1))
+        );
+        assert_eq!(history.len(), 2);
+    }
+
+    #[test]
+    fn test_diff() {
+        let history = record(&CrateMover9001);
+        assert_eq!(
+            history.diff(0, 1),
+            Some(vec![
+                StackDiff {
+                    stack: 1,
+                    removed: vec![],
+                    added: stack_from("D"),
+                },
+                StackDiff {
+                    stack: 2,
+                    removed: stack_from("D"),
+                    added: vec![],
+                },
+            ])
+        );
+        assert_eq!(history.diff(3, 3), Some(vec![]));
+        assert_eq!(
+            history.diff(4, 0).unwrap()[2],
+            StackDiff {
+                stack: 3,
+                removed: stack_from("ZND"),
+                added: vec![],
+            }
+        );
+        assert_eq!(history.diff(0, 9), None);
+    }
+}
@@ -1,80 +1,258 @@
+mod crane;
+mod drawing;
+mod history;
+mod planner;
+
+use crane::{crate_mover_from_name, CrateMover, CrateMover9000, CrateMover9001};
+use drawing::{parse_puzzle_input, render_stacks, RenderOptions};
+use history::OpHistory;
+use planner::{format_ops, plan_moves, PlanLimits, Target};
+use std::fmt;
+
 fn main() {
-    let data = get_puzzle_input();
-    let stacks = get_stacks();
+    let (stacks, data, offset) = parse_puzzle_input(get_puzzle_input()).unwrap();
+    let report = |error: OpError| error.with_line_offset(offset);
 
-    let part1 = part1(data, &mut stacks.clone());
-    let part1_str = part1.iter().collect::<String>();
+    let part1 = part1(data, &mut stacks.clone()).map_err(report).unwrap();
+    let part1_str = format_tops(&part1, "-");
     println!("Part 1: {}", part1_str);
 
-    let part2 = part2(data, &mut stacks.clone());
-    let part2_str = part2.iter().collect::<String>();
+    let part2 = part2(data, &mut stacks.clone()).map_err(report).unwrap();
+    let part2_str = format_tops(&part2, "-");
     println!("Part 2: {}", part2_str);
+
+    // Replay part 2 and show what the first half of the operations changed
+    let mut history = OpHistory::new(stacks.clone());
+    for op in get_ops(data).map_err(report).unwrap() {
+        history.apply(op, &CrateMover9001).unwrap();
+    }
+    let middle = history.len() / 2;
+    history.seek(middle);
+    let cost: u64 = history.applied_ops()[..history.step()]
+        .iter()
+        .map(|applied| applied.cost)
+        .sum();
+    println!("After {} operations (cost {}):", middle, cost);
+    for diff in history.diff(0, middle).unwrap() {
+        println!(
+            "  stack {}: -{} +{}",
+            diff.stack,
+            format_tops(&diff.removed.into_iter().map(Some).collect::<Vec<_>>(), ""),
+            format_tops(&diff.added.into_iter().map(Some).collect::<Vec<_>>(), "")
+        );
+    }
+    history.seek(history.len());
+    println!(
+        "Part 2 replayed: {}",
+        format_tops(&top_crates(history.stacks()), "-")
+    );
+    let options = RenderOptions {
+        max_height: Some(12),
+        moved_by: history.applied_ops().last().map(|applied| &applied.op),
+    };
+    print!("{}", render_stacks(history.stacks(), &options));
+
+    // Look for the shortest ways to where the first operations lead
+    let limits = PlanLimits {
+        max_ops: 4,
+        max_nodes: 1_000_000,
+    };
+    let targets = [
+        Target::Stacks(history.stacks_at(3).unwrap()),
+        Target::Tops(top_crates(&history.stacks_at(4).unwrap())),
+    ];
+    for target in targets {
+        match plan_moves(&stacks, &target, &CrateMover9001, &limits) {
+            Ok(ops) => {
+                let run = process_stack_with_crate_mover(
+                    &format_ops(&ops),
+                    &mut stacks.clone(),
+                    &CrateMover9001,
+                    Mode::Strict,
+                )
+                .unwrap();
+                println!(
+                    "Plan of {} operations to {}:",
+                    ops.len(),
+                    format_tops(&run.tops, "-")
+                );
+                print!("{}", format_ops(&ops));
+            }
+            Err(error) => println!("No plan found: {:?}", error),
+        }
+    }
+
+    // Compare other crate movers given by name on the command line
+    for name in std::env::args().skip(1) {
+        match crate_mover_from_name(&name) {
+            Some(crate_mover) => {
+                let run = process_stack_with_crate_mover(
+                    data,
+                    &mut stacks.clone(),
+                    crate_mover.as_ref(),
+                    Mode::Lenient,
+                )
+                .map_err(report)
+                .unwrap();
+                for warning in run.warnings {
+                    println!("{}: skipped {:?}", name, report(warning));
+                }
+                println!(
+                    "{}: {} (cost {})",
+                    name,
+                    format_tops(&run.tops, "-"),
+                    run.cost
+                );
+            }
+            None => println!("{}: unknown crate mover", name),
+        }
+    }
 }
 
 fn get_puzzle_input() -> &'static str {
     include_str!("../input.txt")
 }
 
-// [Q]         [N]             [N]
-// [H]     [B] [D]             [S] [M]
-// [C]     [Q] [J]         [V] [Q] [D]
-// [T]     [S] [Z] [F]     [J] [J] [W]
-// [N] [G] [T] [S] [V]     [B] [C] [C]
-// [S] [B] [R] [W] [D] [J] [Q] [R] [Q]
-// [V] [D] [W] [G] [P] [W] [N] [T] [S]
-// [B] [W] [F] [L] [M] [F] [L] [G] [J]
-//  1   2   3   4   5   6   7   8   9
-fn get_stacks() -> Stacks {
-    vec![
-        vec!['B', 'V', 'S', 'N', 'T', 'C', 'H', 'Q'],
-        vec!['W', 'D', 'B', 'G'],
-        vec!['F', 'W', 'R', 'T', 'S', 'Q', 'B'],
-        vec!['L', 'G', 'W', 'S', 'Z', 'J', 'D', 'N'],
-        vec!['M', 'P', 'D', 'V', 'F'],
-        vec!['F', 'W', 'J'],
-        vec!['L', 'N', 'Q', 'B', 'J', 'V'],
-        vec!['G', 'T', 'R', 'C', 'J', 'Q', 'S', 'N'],
-        vec!['J', 'S', 'Q', 'C', 'W', 'D', 'M'],
-    ]
+// Label of a crate, like "A" or "AB12"
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Crate(String);
+
+impl fmt::Display for Crate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for Crate {
+    fn from(label: &str) -> Self {
+        Crate(label.to_string())
+    }
+}
+
+impl From<char> for Crate {
+    fn from(label: char) -> Self {
+        Crate(label.to_string())
+    }
 }
 
-type Crate = char;
 type CrateStack = Vec<Crate>;
 type Stacks = Vec<CrateStack>;
 
-enum CrateMover {
-    CreateMover9000,
-    CreateMover9001,
+// Top crate of each stack, None for an empty stack
+fn top_crates(stacks: &Stacks) -> Vec<Option<Crate>> {
+    stacks.iter().map(|stack| stack.last().cloned()).collect()
+}
+
+// Write the top crates like the puzzle answer, with `empty` for the empty stacks
+fn format_tops(tops: &[Option<Crate>], empty: &str) -> String {
+    tops.iter()
+        .map(|top| match top {
+            Some(top) => top.to_string(),
+            None => empty.to_string(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    // Stop on the first invalid line or operation
+    Strict,
+    // Skip invalid lines and operations, reporting them as warnings
+    Lenient,
+}
+
+// Error of an operation, `line` is the 1-based line in the moves part of
+// the input until `with_line_offset` makes it a line of the whole input
+#[derive(Debug, PartialEq)]
+enum OpError {
+    Parse {
+        line: usize,
+        error: OpParseError,
+    },
+    // `stacks` are the stacks the operation was applied to
+    Apply {
+        line: usize,
+        error: ApplyOpError,
+        stacks: Stacks,
+    },
+}
+
+impl OpError {
+    // `offset` is the number of input lines before the moves
+    fn with_line_offset(mut self, offset: usize) -> Self {
+        match &mut self {
+            OpError::Parse { line, .. } | OpError::Apply { line, .. } => *line += offset,
+        }
+        self
+    }
+}
+
+// Top crate of each stack after all the operations, the total cost
+// reported by the crate mover and the errors skipped in lenient mode
+#[derive(Debug, PartialEq)]
+struct CraneRun {
+    tops: Vec<Option<Crate>>,
+    cost: u64,
+    warnings: Vec<OpError>,
 }
 
 fn process_stack_with_crate_mover(
     data: &str,
     stacks: &mut Stacks,
-    crate_mover: &CrateMover,
-) -> Vec<Crate> {
-    let ops = get_ops(data);
-    _ = apply_ops(&ops, stacks, &crate_mover);
-    stacks
-        .iter()
-        .map(|stack| stack.last().unwrap_or(&' ').clone())
-        .collect()
+    crate_mover: &dyn CrateMover,
+    mode: Mode,
+) -> Result<CraneRun, OpError> {
+    let mut cost = 0;
+    let mut warnings = Vec::new();
+    for (line, op) in parse_ops(data) {
+        let error = match op {
+            // A failed operation leaves the stacks unchanged
+            Ok(op) => match apply_op(&op, stacks, crate_mover) {
+                Ok(op_cost) => {
+                    cost += op_cost;
+                    continue;
+                }
+                Err(error) => OpError::Apply {
+                    line,
+                    error,
+                    stacks: stacks.clone(),
+                },
+            },
+            Err(error) => OpError::Parse { line, error },
+        };
+        match mode {
+            Mode::Strict => return Err(error),
+            Mode::Lenient => warnings.push(error),
+        }
+    }
+    Ok(CraneRun {
+        tops: top_crates(stacks),
+        cost,
+        warnings,
+    })
 }
 
-fn part1(data: &str, stacks: &mut Stacks) -> Vec<Crate> {
-    process_stack_with_crate_mover(data, stacks, &CrateMover::CreateMover9000)
+fn part1(data: &str, stacks: &mut Stacks) -> Result<Vec<Option<Crate>>, OpError> {
+    Ok(process_stack_with_crate_mover(data, stacks, &CrateMover9000, Mode::Strict)?.tops)
 }
 
-fn part2(data: &str, stacks: &mut Stacks) -> Vec<Crate> {
-    process_stack_with_crate_mover(data, stacks, &CrateMover::CreateMover9001)
+fn part2(data: &str, stacks: &mut Stacks) -> Result<Vec<Option<Crate>>, OpError> {
+    Ok(process_stack_with_crate_mover(data, stacks, &CrateMover9001, Mode::Strict)?.tops)
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 struct Op {
     from: usize,
     to: usize,
     count: usize,
 }
 
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum OpParseError {
     MissingMoveKeyWord,
@@ -89,7 +267,7 @@ enum OpParseError {
 // Get the operation from a line of input
 // Input should be formatted "move <count> from <from> to <to>"
 fn get_op(line: &str) -> Result<Op, OpParseError> {
-    let mut parts = line.split(" ");
+    let mut parts = line.split(' ');
     let move_keyword = parts.next().ok_or(OpParseError::MissingMoveKeyWord)?;
     if move_keyword != "move" {
         return Err(OpParseError::MissingMoveKeyWord);
@@ -131,10 +309,24 @@ fn get_op(line: &str) -> Result<Op, OpParseError> {
     })
 }
 
-fn get_ops(data: &str) -> Vec<Op> {
-    data.lines().filter_map(|line| get_op(line).ok()).collect()
+// Parse each non empty line with its 1-based line number
+fn parse_ops(data: &str) -> Vec<(usize, Result<Op, OpParseError>)> {
+    data.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| (idx + 1, get_op(line)))
+        .collect()
+}
+
+// Parse all the operations, failing on the first invalid line
+fn get_ops(data: &str) -> Result<Vec<Op>, OpError> {
+    parse_ops(data)
+        .into_iter()
+        .map(|(line, op)| op.map_err(|error| OpError::Parse { line, error }))
+        .collect()
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq)]
 enum ApplyOpError {
     InvalidFromIndex(usize),
@@ -142,17 +334,27 @@ enum ApplyOpError {
     InvalidCount(usize),
 }
 
-fn apply_op(op: &Op, stacks: &mut Stacks, crate_mover: &CrateMover) -> Option<ApplyOpError> {
-    if op.from == op.to {
-        return None;
-    }
-
+// Return the cost of the operation given by the crate mover. The
+// operation is checked first, so the stacks are untouched on error.
+fn apply_op(
+    op: &Op,
+    stacks: &mut Stacks,
+    crate_mover: &dyn CrateMover,
+) -> Result<u64, ApplyOpError> {
     if op.from < 1 || op.from > stacks.len() {
-        return Some(ApplyOpError::InvalidFromIndex(op.from));
+        return Err(ApplyOpError::InvalidFromIndex(op.from));
     }
 
     if op.to < 1 || op.to > stacks.len() {
-        return Some(ApplyOpError::InvalidToIndex(op.to));
+        return Err(ApplyOpError::InvalidToIndex(op.to));
+    }
+
+    if op.count > stacks[op.from - 1].len() {
+        return Err(ApplyOpError::InvalidCount(op.count));
+    }
+
+    if op.from == op.to {
+        return Ok(0);
     }
 
     let max_idx = std::cmp::max(op.from, op.to);
@@ -172,34 +374,14 @@ fn apply_op(op: &Op, stacks: &mut Stacks, crate_mover: &CrateMover) -> Option<Ap
         }
     };
 
-    if op.count > from_stack.len() {
-        return Some(ApplyOpError::InvalidCount(op.count));
-    }
-
-    let from_stack_ref = &from_stack[from_stack.len() - op.count..];
-    match crate_mover {
-        CrateMover::CreateMover9000 => to_stack.extend(from_stack_ref.iter().rev()),
-        CrateMover::CreateMover9001 => to_stack.extend(from_stack_ref.iter()),
-    }
-    from_stack.resize(from_stack.len() - op.count, ' ');
-
-    None
-}
-
-fn apply_ops(
-    ops: &Vec<Op>,
-    stacks: &mut Stacks,
-    crate_mover: &CrateMover,
-) -> Vec<Option<ApplyOpError>> {
-    ops.iter()
-        .map(|op| apply_op(op, stacks, &crate_mover))
-        .collect()
+    Ok(crate_mover.move_crates(from_stack, to_stack, op.count))
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
-    static _TEST_DATA: &str = "move 1 from 2 to 1
+    pub(crate) static _TEST_DATA: &str = "move 1 from 2 to 1
 move 3 from 1 to 3
 move 2 from 2 to 1
 move 1 from 1 to 2
@@ -285,8 +467,24 @@ move 1 from 1 to 2
         );
     }
 
-    fn _get_tests_stacks() -> Stacks {
-        vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]
+    // Build stacks from strings of single letter crates, bottom first
+    pub(crate) fn stacks_from(stacks: &[&str]) -> Stacks {
+        stacks.iter().map(|stack| stack_from(stack)).collect()
+    }
+
+    pub(crate) fn stack_from(crates: &str) -> CrateStack {
+        crates.chars().map(Crate::from).collect()
+    }
+
+    // Build the top crates from a puzzle answer, ' ' for an empty stack
+    pub(crate) fn tops_from(tops: &str) -> Vec<Option<Crate>> {
+        tops.chars()
+            .map(|top| (top != ' ').then(|| Crate::from(top)))
+            .collect()
+    }
+
+    pub(crate) fn _get_tests_stacks() -> Stacks {
+        stacks_from(&["ZN", "MCD", "P"])
     }
 
     #[test]
@@ -300,12 +498,12 @@ move 1 from 1 to 2
                     to: 1,
                 },
                 &mut stacks,
-                &CrateMover::CreateMover9000
+                &CrateMover9000
             ),
-            None
+            Ok(1)
         );
 
-        assert_eq!(stacks, vec![vec!['Z', 'N', 'D'], vec!['M', 'C'], vec!['P']]);
+        assert_eq!(stacks, stacks_from(&["ZND", "MC", "P"]));
 
         assert_eq!(
             apply_op(
@@ -315,29 +513,203 @@ move 1 from 1 to 2
                     to: 3,
                 },
                 &mut stacks,
-                &CrateMover::CreateMover9000
+                &CrateMover9000
             ),
-            None
+            Ok(3)
         );
 
+        assert_eq!(stacks, stacks_from(&["", "MC", "PDNZ"]));
+    }
+
+    #[test]
+    fn test_part1() {
         assert_eq!(
-            stacks,
-            vec![vec![], vec!['M', 'C'], vec!['P', 'D', 'N', 'Z']]
+            part1(_TEST_DATA, &mut _get_tests_stacks()).unwrap(),
+            tops_from("CMZ")
+        )
+    }
+
+    #[test]
+    fn test_process_stack_cost() {
+        let run = process_stack_with_crate_mover(
+            _TEST_DATA,
+            &mut _get_tests_stacks(),
+            &CrateMover9000,
+            Mode::Strict,
+        );
+        assert_eq!(
+            run,
+            Ok(CraneRun {
+                tops: tops_from("CMZ"),
+                cost: 7,
+                warnings: vec![],
+            })
         );
+
+        let crate_mover = crate_mover_from_name("capacity=2").unwrap();
+        let run = process_stack_with_crate_mover(
+            _TEST_DATA,
+            &mut _get_tests_stacks(),
+            crate_mover.as_ref(),
+            Mode::Strict,
+        )
+        .unwrap();
+        assert_eq!(run.tops, tops_from("MCZ"));
+        assert_eq!(run.cost, 5);
     }
 
+    static _INVALID_DATA: &str = "move 1 from 2 to 1
+move 3 from 1 to 3
+
+mvoe 2 from 2 to 1
+move 2 from 1 to 2
+move 1 from 4 to 2
+move 1 from 2 to 1
+";
+
     #[test]
-    fn test_part1() {
+    fn test_strict_mode() {
+        assert_eq!(
+            get_ops(_INVALID_DATA).unwrap_err(),
+            OpError::Parse {
+                line: 4,
+                error: OpParseError::MissingMoveKeyWord
+            }
+        );
+
+        // Fails on the first invalid op with the stacks it was applied to
+        let data = "move 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 1 to 2\n";
         assert_eq!(
-            part1(_TEST_DATA, &mut _get_tests_stacks()),
-            vec!['C', 'M', 'Z']
+            process_stack_with_crate_mover(
+                data,
+                &mut _get_tests_stacks(),
+                &CrateMover9000,
+                Mode::Strict
+            ),
+            Err(OpError::Apply {
+                line: 3,
+                error: ApplyOpError::InvalidCount(2),
+                stacks: stacks_from(&["", "MC", "PDNZ"]),
+            })
+        );
+        assert_eq!(
+            part1(
+                "move 1 from 3 to 3\nmove 1 from 4 to 4",
+                &mut _get_tests_stacks()
+            ),
+            Err(OpError::Apply {
+                line: 2,
+                error: ApplyOpError::InvalidFromIndex(4),
+                stacks: _get_tests_stacks(),
+            })
+        );
+
+        // Moving to the same stack still needs enough crates
+        assert_eq!(
+            process_stack_with_crate_mover(
+                "move 9 from 1 to 1",
+                &mut _get_tests_stacks(),
+                &CrateMover9000,
+                Mode::Strict
+            ),
+            Err(OpError::Apply {
+                line: 1,
+                error: ApplyOpError::InvalidCount(9),
+                stacks: _get_tests_stacks(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_line_in_input() {
+        let input = "    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+mvoe 3 from 1 to 3
+";
+        let (mut stacks, data, offset) = parse_puzzle_input(input).unwrap();
+        assert_eq!(
+            part1(data, &mut stacks).map_err(|error| error.with_line_offset(offset)),
+            Err(OpError::Parse {
+                line: 7,
+                error: OpParseError::MissingMoveKeyWord
+            })
+        );
+    }
+
+    #[test]
+    fn test_lenient_mode() {
+        let run = process_stack_with_crate_mover(
+            _INVALID_DATA,
+            &mut _get_tests_stacks(),
+            &CrateMover9000,
+            Mode::Lenient,
         )
+        .unwrap();
+        assert_eq!(
+            run.warnings,
+            vec![
+                OpError::Parse {
+                    line: 4,
+                    error: OpParseError::MissingMoveKeyWord
+                },
+                OpError::Apply {
+                    line: 5,
+                    error: ApplyOpError::InvalidCount(2),
+                    stacks: stacks_from(&["", "MC", "PDNZ"]),
+                },
+                OpError::Apply {
+                    line: 6,
+                    error: ApplyOpError::InvalidFromIndex(4),
+                    stacks: stacks_from(&["", "MC", "PDNZ"]),
+                },
+            ]
+        );
+        assert_eq!(run.tops, tops_from("CMZ"));
+        assert_eq!(run.cost, 5);
     }
+
     #[test]
     fn test_part2() {
         assert_eq!(
-            part2(_TEST_DATA, &mut _get_tests_stacks()),
-            vec!['M', 'C', 'D']
+            part2(_TEST_DATA, &mut _get_tests_stacks()).unwrap(),
+            tops_from("MCD")
+        )
+    }
+
+    #[test]
+    fn test_empty_stack_tops() {
+        let mut stacks = vec![vec![Crate::from("AB12")], vec![], stack_from("C")];
+        let tops = top_crates(&stacks);
+        assert_eq!(
+            tops,
+            vec![Some(Crate::from("AB12")), None, Some(Crate::from('C'))]
+        );
+        assert_eq!(format_tops(&tops, "-"), "AB12-C");
+
+        // A multi-character crate moves as a single crate
+        let run = process_stack_with_crate_mover(
+            "move 1 from 1 to 2\n",
+            &mut stacks.clone(),
+            &CrateMover9000,
+            Mode::Strict,
+        )
+        .unwrap();
+        assert_eq!(format_tops(&run.tops, "-"), "-AB12C");
+
+        stacks[0].push(Crate::from("long-name"));
+        assert_eq!(format_tops(&top_crates(&stacks), "-"), "long-name-C");
+
+        let run = process_stack_with_crate_mover(
+            "move 3 from 1 to 3\n",
+            &mut stacks_from(&["ZND", "MC", "P"]),
+            &CrateMover9001,
+            Mode::Strict,
         )
+        .unwrap();
+        assert_eq!(run.tops, tops_from(" CD"));
     }
 }
@@ -0,0 +1,252 @@
+use crate::crane::CrateMover;
+use crate::{apply_op, Crate, Op, Stacks};
+
+// What the stacks should look like at the end of the plan
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    // Top crate of each stack, None for an empty stack
+    Tops(Vec<Option<Crate>>),
+    // Every crate of every stack
+    Stacks(Stacks),
+}
+
+impl Target {
+    fn is_reached(&self, stacks: &Stacks) -> bool {
+        self.misplaced_stacks(stacks) == 0
+    }
+
+    // Number of stacks that don't match the target yet
+    fn misplaced_stacks(&self, stacks: &Stacks) -> usize {
+        match self {
+            Target::Tops(tops) => stacks
+                .iter()
+                .zip(tops)
+                .filter(|(stack, top)| stack.last() != top.as_ref())
+                .count(),
+            Target::Stacks(target) => stacks
+                .iter()
+                .zip(target)
+                .filter(|(stack, target)| stack != target)
+                .count(),
+        }
+    }
+
+    // Check the target can be made of the crates of `stacks`
+    fn is_reachable_from(&self, stacks: &Stacks) -> bool {
+        let mut available: Vec<Crate> = stacks.iter().flatten().cloned().collect();
+        available.sort();
+        match self {
+            Target::Tops(tops) => {
+                if tops.len() != stacks.len() {
+                    return false;
+                }
+                let mut wanted: Vec<Crate> = tops.iter().flatten().cloned().collect();
+                wanted.sort();
+                // Every wanted crate has its own copy in the available ones
+                let mut available = available.iter();
+                wanted
+                    .iter()
+                    .all(|crate_name| available.any(|c| c == crate_name))
+            }
+            Target::Stacks(target) => {
+                let mut wanted: Vec<Crate> = target.iter().flatten().cloned().collect();
+                wanted.sort();
+                target.len() == stacks.len() && wanted == available
+            }
+        }
+    }
+
+    // Lower bound of the number of operations left:
+    // an operation only changes the stacks it moves crates from and to
+    fn min_ops_left(&self, stacks: &Stacks) -> usize {
+        self.misplaced_stacks(stacks).div_ceil(2)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PlanError {
+    // The target uses crates or stacks that don't exist
+    Unreachable,
+    // No plan was found within the limits
+    LimitReached,
+}
+
+pub struct PlanLimits {
+    // Longest plan searched
+    pub max_ops: usize,
+    // Number of stacks arrangements explored before giving up
+    pub max_nodes: usize,
+}
+
+struct Search<'a> {
+    target: &'a Target,
+    crate_mover: &'a dyn CrateMover,
+    max_nodes: usize,
+    nodes: usize,
+    ops: Vec<Op>,
+    // Arrangements along the current path, to never loop back to one of them
+    path: Vec<Stacks>,
+}
+
+impl Search<'_> {
+    // Depth first search of a plan of at most `max_ops` operations
+    fn search(&mut self, stacks: &Stacks, max_ops: usize) -> Result<bool, PlanError> {
+        if self.target.is_reached(stacks) {
+            return Ok(true);
+        }
+        if self.ops.len() + self.target.min_ops_left(stacks) > max_ops {
+            return Ok(false);
+        }
+        self.nodes += 1;
+        if self.nodes > self.max_nodes {
+            return Err(PlanError::LimitReached);
+        }
+
+        for from in 1..=stacks.len() {
+            for to in (1..=stacks.len()).filter(|to| *to != from) {
+                for count in 1..=stacks[from - 1].len() {
+                    let op = Op { from, to, count };
+                    let mut next = stacks.clone();
+                    if apply_op(&op, &mut next, self.crate_mover).is_err()
+                        || self.path.contains(&next)
+                    {
+                        continue;
+                    }
+                    self.ops.push(op);
+                    self.path.push(next.clone());
+                    if self.search(&next, max_ops)? {
+                        return Ok(true);
+                    }
+                    self.path.pop();
+                    self.ops.pop();
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+// Find one of the shortest lists of operations that turn `stacks` into `target`
+// with iterative deepening A*, the result is in the same format as the puzzle
+pub fn plan_moves(
+    stacks: &Stacks,
+    target: &Target,
+    crate_mover: &dyn CrateMover,
+    limits: &PlanLimits,
+) -> Result<Vec<Op>, PlanError> {
+    if !target.is_reachable_from(stacks) {
+        return Err(PlanError::Unreachable);
+    }
+    let mut search = Search {
+        target,
+        crate_mover,
+        max_nodes: limits.max_nodes,
+        nodes: 0,
+        ops: Vec::new(),
+        path: vec![stacks.clone()],
+    };
+    for max_ops in target.min_ops_left(stacks)..=limits.max_ops {
+        if search.search(stacks, max_ops)? {
+            return Ok(search.ops);
+        }
+    }
+    Err(PlanError::LimitReached)
+}
+
+// Write the operations one per line, like the puzzle input
+pub fn format_ops(ops: &[Op]) -> String {
+    ops.iter().map(|op| format!("{}\n", op)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crane::{CrateMover9000, CrateMover9001};
+    use crate::tests::{_get_tests_stacks, stacks_from, tops_from};
+    use crate::{process_stack_with_crate_mover, Mode};
+
+    fn limits() -> PlanLimits {
+        PlanLimits {
+            max_ops: 6,
+            max_nodes: 100_000,
+        }
+    }
+
+    #[test]
+    fn test_plan_tops() {
+        // The puzzle example needs 4 operations, the planner finds shorter
+        let target = Target::Tops(tops_from("CMZ"));
+        let ops = plan_moves(&_get_tests_stacks(), &target, &CrateMover9000, &limits()).unwrap();
+        assert_eq!(ops.len(), 2);
+
+        // Feed the plan back as puzzle input
+        let run = process_stack_with_crate_mover(
+            &format_ops(&ops),
+            &mut _get_tests_stacks(),
+            &CrateMover9000,
+            Mode::Strict,
+        )
+        .unwrap();
+        assert_eq!(run.tops, tops_from("CMZ"));
+
+        let ops = plan_moves(
+            &_get_tests_stacks(),
+            &Target::Tops(tops_from("NDP")),
+            &CrateMover9000,
+            &limits(),
+        )
+        .unwrap();
+        assert!(ops.is_empty());
+    }
+
+    #[test]
+    fn test_plan_stacks() {
+        let target = Target::Stacks(stacks_from(&["", "MCDZN", "P"]));
+        let ops = plan_moves(&_get_tests_stacks(), &target, &CrateMover9001, &limits()).unwrap();
+        assert_eq!(format_ops(&ops), "move 2 from 1 to 2\n");
+
+        let ops = plan_moves(&_get_tests_stacks(), &target, &CrateMover9000, &limits()).unwrap();
+        assert_eq!(ops.len(), 2);
+        let mut stacks = _get_tests_stacks();
+        process_stack_with_crate_mover(
+            &format_ops(&ops),
+            &mut stacks,
+            &CrateMover9000,
+            Mode::Strict,
+        )
+        .unwrap();
+        assert_eq!(Target::Stacks(stacks), target);
+    }
+
+    #[test]
+    fn test_plan_errors() {
+        let plan =
+            |target: Target| plan_moves(&_get_tests_stacks(), &target, &CrateMover9001, &limits());
+        assert_eq!(
+            plan(Target::Tops(tops_from("XMZ"))),
+            Err(PlanError::Unreachable)
+        );
+        assert_eq!(
+            plan(Target::Tops(tops_from("ZZP"))),
+            Err(PlanError::Unreachable)
+        );
+        assert_eq!(
+            plan(Target::Stacks(stacks_from(&["ZNMCDP"]))),
+            Err(PlanError::Unreachable)
+        );
+
+        let limits = PlanLimits {
+            max_ops: 1,
+            max_nodes: 100_000,
+        };
+        assert_eq!(
+            plan_moves(
+                &_get_tests_stacks(),
+                &Target::Tops(tops_from("CMZ")),
+                &CrateMover9000,
+                &limits
+            ),
+            Err(PlanError::LimitReached)
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use itertools::Itertools;
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/marker.rs"]
+mod marker;
+
+// Previous implementation, kept as a baseline for the ring buffer version
+fn vec_find_idx_after_unique_char_count(input: &str, required_len: usize) -> Option<usize> {
+    let mut last_chars = Vec::<char>::new();
+
+    for (idx, char) in input.chars().enumerate() {
+        if !char.is_alphabetic() {
+            return None;
+        }
+
+        if last_chars.len() == required_len {
+            last_chars.remove(0);
+        }
+
+        last_chars.push(char);
+
+        // Check that last_chars are unique
+        if last_chars.iter().unique().count() == required_len {
+            return Some(idx + 1);
+        }
+    }
+    None
+}
+
+// Stream repeating `len - 1` distinct letters (at most the whole
+// alphabet), so no window of `len` bytes is a marker and the whole
+// stream is scanned
+fn stream_without_marker(len: usize, size: usize) -> String {
+    let letters = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    let period = (len - 1).min(letters.len());
+    letters[..period]
+        .iter()
+        .cycle()
+        .take(size)
+        .map(|b| *b as char)
+        .collect()
+}
+
+fn bench_markers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_marker");
+    group.sample_size(10);
+    for len in [4, 14, 1000] {
+        let data = stream_without_marker(len, 64 * 1024);
+        group.bench_with_input(BenchmarkId::new("vec", len), &data, |b, data| {
+            b.iter(|| vec_find_idx_after_unique_char_count(black_box(data), len))
+        });
+        let options = marker::MarkerOptions::default();
+        group.bench_with_input(BenchmarkId::new("ring_buffer", len), &data, |b, data| {
+            b.iter(|| marker::find_marker(black_box(data.as_bytes()), len, &options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_markers);
+criterion_main!(benches);
@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+use std::io::Read;
+use std::ops::ControlFlow;
+
+use crate::marker::{for_each_byte, MarkerDetector, MarkerError, MarkerOptions, Symbol};
+
+// Payload following a marker, up to the next marker of the same length
+#[derive(Debug, PartialEq)]
+pub struct Frame {
+    pub marker_len: usize,
+    // Offset of the first byte of the marker
+    pub marker_offset: usize,
+    // Offset of the first byte of the payload, as returned by `find_marker`
+    pub offset: usize,
+    pub payload: Vec<u8>,
+    // False when the stream ends before the next marker
+    pub terminated: bool,
+}
+
+// Frames of one marker length being decoded
+struct Channel {
+    detector: MarkerDetector,
+    marker_len: usize,
+    // Offsets of the bytes in the detector window, skipped bytes
+    // can make the marker longer than `marker_len` in the stream
+    window_offsets: VecDeque<usize>,
+    current: Option<Frame>,
+}
+
+impl Channel {
+    fn push(&mut self, symbol: &Symbol, byte: u8, offset: usize, frames: &mut Vec<Frame>) {
+        if let Some(frame) = &mut self.current {
+            frame.payload.push(byte);
+        }
+        match symbol {
+            Symbol::Push => {}
+            Symbol::Reset => {
+                self.detector.reset();
+                self.window_offsets.clear();
+                return;
+            }
+            Symbol::Skip => return,
+        }
+
+        if self.window_offsets.len() == self.marker_len {
+            self.window_offsets.pop_front();
+        }
+        self.window_offsets.push_back(offset);
+        if !self.detector.push(byte) {
+            return;
+        }
+
+        // The marker bytes aren't part of the previous payload
+        let marker_offset = self.window_offsets[0];
+        if let Some(mut frame) = self.current.take() {
+            frame.payload.truncate(marker_offset - frame.offset);
+            frame.terminated = true;
+            frames.push(frame);
+        }
+        self.current = Some(Frame {
+            marker_len: self.marker_len,
+            marker_offset,
+            offset: offset + 1,
+            payload: Vec::new(),
+            terminated: false,
+        });
+        // Markers don't overlap, the next one starts after this one
+        self.detector.reset();
+        self.window_offsets.clear();
+    }
+}
+
+// Split a datastream into frames for each marker length. Every marker
+// starts a frame that ends at the next marker of the same length, and
+// the last frame of each length is reported as not terminated. Payloads
+// keep the bytes outside of the alphabet. Frames are sorted by offset.
+pub fn demultiplex<R: Read>(
+    reader: R,
+    marker_lens: &[usize],
+    options: &MarkerOptions,
+) -> Result<Vec<Frame>, MarkerError> {
+    let mut channels: Vec<Channel> = marker_lens
+        .iter()
+        .map(|len| Channel {
+            detector: MarkerDetector::new(*len),
+            marker_len: *len,
+            window_offsets: VecDeque::with_capacity(*len),
+            current: None,
+        })
+        .collect();
+    let mut frames = Vec::new();
+
+    for_each_byte(reader, |offset, byte| {
+        let symbol = options.symbol(offset, byte)?;
+        for channel in &mut channels {
+            channel.push(&symbol, byte, offset, &mut frames);
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+
+    frames.extend(channels.into_iter().filter_map(|channel| channel.current));
+    frames.sort_by_key(|frame| (frame.marker_offset, frame.marker_len));
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marker::OutOfAlphabet;
+
+    fn frame(marker_len: usize, offset: usize, payload: &str, terminated: bool) -> Frame {
+        Frame {
+            marker_len,
+            marker_offset: offset - marker_len,
+            offset,
+            payload: payload.as_bytes().to_vec(),
+            terminated,
+        }
+    }
+
+    fn demux(data: &[u8], marker_lens: &[usize]) -> Vec<Frame> {
+        demultiplex(data, marker_lens, &MarkerOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_demultiplex() {
+        assert_eq!(demux(b"", &[4]), vec![]);
+        assert_eq!(demux(b"aaaa", &[4]), vec![]);
+
+        let frames = demux(b"aabcdxxeefghzz", &[4]);
+        assert_eq!(
+            frames,
+            vec![frame(4, 5, "xxe", true), frame(4, 12, "zz", false)]
+        );
+
+        // Back to back markers give empty payloads
+        let frames = demux(b"abcdefgh", &[4]);
+        assert_eq!(frames, vec![frame(4, 4, "", true), frame(4, 8, "", false)]);
+    }
+
+    #[test]
+    fn test_demultiplex_lengths() {
+        let frames = demux(b"abcdefaab\nghij", &[3, 6]);
+        assert_eq!(
+            frames,
+            vec![
+                frame(3, 3, "", true),
+                frame(6, 6, "aab\nghij", false),
+                frame(3, 6, "aab\n", true),
+                frame(3, 13, "j", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_demultiplex_skip() {
+        let options = MarkerOptions {
+            out_of_alphabet: OutOfAlphabet::Skip,
+            ..Default::default()
+        };
+        // The second marker "x\nef" spans a skipped newline
+        let frames = demultiplex(&b"abcxx\nx\nef"[..], &[3], &options).unwrap();
+        assert_eq!(
+            frames,
+            vec![
+                frame(3, 3, "xx\n", true),
+                Frame {
+                    marker_len: 3,
+                    marker_offset: 6,
+                    offset: 10,
+                    payload: vec![],
+                    terminated: false,
+                },
+            ]
+        );
+
+        let options = MarkerOptions {
+            out_of_alphabet: OutOfAlphabet::Error,
+            ..Default::default()
+        };
+        assert!(matches!(
+            demultiplex(&b"abc\n"[..], &[3], &options),
+            Err(MarkerError::OutOfAlphabet { offset: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_demultiplex_example() {
+        let data = b"mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        let frames = demux(data, &[4, 14]);
+
+        let packets: Vec<usize> = frames
+            .iter()
+            .filter(|frame| frame.marker_len == 4)
+            .map(|frame| frame.offset)
+            .collect();
+        assert_eq!(packets, vec![7, 11, 15, 19, 23, 27]);
+        assert_eq!(frames[0], frame(4, 7, "", true));
+
+        let messages: Vec<&Frame> = frames
+            .iter()
+            .filter(|frame| frame.marker_len == 14)
+            .collect();
+        assert_eq!(messages, vec![&frame(14, 19, "jfqwrcgsmlb", false)]);
+    }
+}
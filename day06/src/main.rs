@@ -1,97 +1,155 @@
-use itertools::Itertools;
+use marker::{Alphabet, MarkerError, MarkerOptions, OutOfAlphabet};
+
+mod demux;
+mod marker;
 
 fn main() {
+    // Alphabet and out of alphabet handling, e.g. `day06 printable skip`
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(options) = options_from_args(&args) else {
+        println!("usage: day06 [alphabetic|printable|any|custom:<symbols>] [reset|skip|error]");
+        return;
+    };
+
     // Read input.txt file
     let input = std::fs::read_to_string("input.txt").unwrap();
 
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
-}
-
-fn part1(input: &str) -> i32 {
-    find_first_marker_offset(input).unwrap() as i32
-}
-
-fn part2(input: &str) -> i32 {
-    find_start_of_message(input).unwrap() as i32
-}
-
-fn find_idx_after_unique_char_count(input: &str, required_len: usize) -> Option<usize> {
-    let mut last_chars = Vec::<char>::new();
-
-    for (idx, char) in input.chars().enumerate() {
-        if !char.is_alphabetic() {
-            return None;
+    for (name, result) in [
+        ("Part 1", find_first_marker_offset(&input, &options)),
+        ("Part 2", find_start_of_message(&input, &options)),
+    ] {
+        match result {
+            Ok(Some(offset)) => println!("{}: {}", name, offset),
+            Ok(None) => println!("{}: no marker", name),
+            Err(error) => println!("{}: {}", name, error),
         }
+    }
 
-        if last_chars.len() == required_len {
-            last_chars.remove(0);
+    let file = std::fs::File::open("input.txt").unwrap();
+    let frames = match demux::demultiplex(file, &[4, 14], &options) {
+        Ok(frames) => frames,
+        Err(error) => {
+            println!("Demultiplexing failed: {}", error);
+            return;
         }
-
-        last_chars.push(char);
-
-        // Check that last_chars are unique
-        if last_chars.iter().unique().count() == required_len {
-            return Some(idx + 1);
+    };
+    for (name, marker_len) in [("Packets", 4), ("Messages", 14)] {
+        let frames: Vec<_> = frames
+            .iter()
+            .filter(|frame| frame.marker_len == marker_len)
+            .collect();
+        let payload: usize = frames.iter().map(|frame| frame.payload.len()).sum();
+        println!(
+            "{}: {} frame(s), {} payload byte(s)",
+            name,
+            frames.len(),
+            payload
+        );
+        for frame in frames.iter().filter(|frame| !frame.terminated) {
+            println!(
+                "  unterminated frame at {}: {} byte(s)",
+                frame.offset,
+                frame.payload.len()
+            );
         }
     }
-    None
 }
 
-fn find_first_marker_offset(input: &str) -> Option<usize> {
-    find_idx_after_unique_char_count(input, 4)
+fn options_from_args(args: &[String]) -> Option<MarkerOptions> {
+    let mut options = MarkerOptions::default();
+    if let Some(alphabet) = args.first() {
+        options.alphabet = Alphabet::from_name(alphabet)?;
+    }
+    if let Some(out_of_alphabet) = args.get(1) {
+        options.out_of_alphabet = OutOfAlphabet::from_name(out_of_alphabet)?;
+    }
+    Some(options)
+}
+
+fn find_idx_after_unique_char_count(
+    input: &str,
+    required_len: usize,
+    options: &MarkerOptions,
+) -> Result<Option<usize>, MarkerError> {
+    marker::find_marker(input.as_bytes(), required_len, options)
 }
 
-fn find_start_of_message(input: &str) -> Option<usize> {
-    find_idx_after_unique_char_count(input, 14)
+fn find_first_marker_offset(
+    input: &str,
+    options: &MarkerOptions,
+) -> Result<Option<usize>, MarkerError> {
+    find_idx_after_unique_char_count(input, 4, options)
 }
 
+fn find_start_of_message(
+    input: &str,
+    options: &MarkerOptions,
+) -> Result<Option<usize>, MarkerError> {
+    find_idx_after_unique_char_count(input, 14, options)
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
+    // Default options, bytes that aren't letters reset the window
+    fn first_marker(input: &str) -> Option<usize> {
+        find_first_marker_offset(input, &MarkerOptions::default()).unwrap()
+    }
+
+    fn start_of_message(input: &str) -> Option<usize> {
+        find_start_of_message(input, &MarkerOptions::default()).unwrap()
+    }
+
     #[test]
     fn test_part1() {
-        assert_eq!(find_first_marker_offset(""), None);
+        assert_eq!(first_marker(""), None);
 
-        assert_eq!(find_first_marker_offset("abc"), None);
+        assert_eq!(first_marker("abc"), None);
 
-        assert_eq!(find_first_marker_offset("ab(c)"), None);
+        assert_eq!(first_marker("ab(c)"), None);
 
-        assert_eq!(find_first_marker_offset("ab(c)d"), None);
+        // Symbols other than letters start a new window
+        assert_eq!(first_marker("ab(c)d"), None);
 
-        assert_eq!(
-            find_first_marker_offset("mjqjpqmgbljsphdztnvjfqwrcgsmlb"),
-            Some(7)
-        );
+        assert_eq!(first_marker("ab(c)defg"), Some(9));
 
-        assert_eq!(
-            find_first_marker_offset("bvwbjplbgvbhsrlpgdmjqwftvncz"),
-            Some(5)
-        );
+        assert_eq!(first_marker("12abcd\n"), Some(6));
 
-        assert_eq!(
-            find_first_marker_offset("nppdvjthqldpwncqszvftbrmjlhg"),
-            Some(6)
-        );
+        assert_eq!(first_marker("mjqjpqmgbljsphdztnvjfqwrcgsmlb"), Some(7));
 
-        assert_eq!(
-            find_first_marker_offset("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"),
-            Some(10)
-        );
+        assert_eq!(first_marker("bvwbjplbgvbhsrlpgdmjqwftvncz"), Some(5));
 
-        assert_eq!(
-            find_first_marker_offset("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"),
-            Some(11)
-        );
+        assert_eq!(first_marker("nppdvjthqldpwncqszvftbrmjlhg"), Some(6));
+
+        assert_eq!(first_marker("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), Some(10));
+
+        assert_eq!(first_marker("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), Some(11));
+    }
+
+    #[test]
+    fn test_options() {
+        let input = "ab(c)d\n";
+        let options = options_from_args(&["alphabetic".into(), "skip".into()]).unwrap();
+        assert_eq!(find_first_marker_offset(input, &options).unwrap(), Some(6));
+
+        let options = options_from_args(&["alphabetic".into(), "error".into()]).unwrap();
+        assert!(matches!(
+            find_first_marker_offset(input, &options),
+            Err(MarkerError::OutOfAlphabet { offset: 2, .. })
+        ));
+
+        let options = options_from_args(&["printable".into()]).unwrap();
+        assert_eq!(find_first_marker_offset(input, &options).unwrap(), Some(4));
+
+        assert!(options_from_args(&["binary".into()]).is_none());
+        assert!(options_from_args(&["any".into(), "abort".into()]).is_none());
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(find_start_of_message(""), None);
-        assert_eq!(find_start_of_message("abc"), None);
-        assert_eq!(
-            find_start_of_message("mjqjpqmgbljsphdztnvjfqwrcgsmlb"),
-            Some(19)
-        );
+        assert_eq!(start_of_message(""), None);
+        assert_eq!(start_of_message("abc"), None);
+        assert_eq!(start_of_message("mjqjpqmgbljsphdztnvjfqwrcgsmlb"), Some(19));
     }
 }
@@ -0,0 +1,362 @@
+use std::fmt;
+use std::io::{self, Read};
+use std::ops::ControlFlow;
+
+// Size of the chunks read from the stream
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+// Detect windows of `len` distinct bytes in a stream, one byte at a time.
+// The last `len` bytes are kept in a ring buffer next to how many times
+// each byte value appears in it, and `duplicates` counts the extra
+// occurrences. Pushing a byte updates both in O(1), whatever the window
+// length.
+pub struct MarkerDetector {
+    window: Vec<u8>,
+    // Next slot of the ring buffer to overwrite
+    head: usize,
+    // Number of bytes in the window, up to `window.len()`
+    filled: usize,
+    counts: [usize; 256],
+    duplicates: usize,
+}
+
+impl MarkerDetector {
+    pub fn new(len: usize) -> Self {
+        assert!(len > 0, "a marker has at least one byte");
+        MarkerDetector {
+            window: vec![0; len],
+            head: 0,
+            filled: 0,
+            counts: [0; 256],
+            duplicates: 0,
+        }
+    }
+
+    // Add a byte to the window, return true when the window is full
+    // and all its bytes are distinct
+    pub fn push(&mut self, byte: u8) -> bool {
+        if self.filled == self.window.len() {
+            let old = self.window[self.head] as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] > 0 {
+                self.duplicates -= 1;
+            }
+        } else {
+            self.filled += 1;
+        }
+
+        self.window[self.head] = byte;
+        self.head = (self.head + 1) % self.window.len();
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] > 1 {
+            self.duplicates += 1;
+        }
+
+        self.filled == self.window.len() && self.duplicates == 0
+    }
+
+    // Empty the window
+    pub fn reset(&mut self) {
+        self.head = 0;
+        self.filled = 0;
+        self.counts = [0; 256];
+        self.duplicates = 0;
+    }
+}
+
+// Bytes that can be part of a marker
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Alphabet {
+    #[default]
+    Alphabetic,
+    // Space and visible ASCII characters
+    PrintableAscii,
+    AnyByte,
+    Custom(Box<[bool; 256]>),
+}
+
+impl Alphabet {
+    pub fn custom(symbols: &[u8]) -> Self {
+        let mut set = [false; 256];
+        for symbol in symbols {
+            set[*symbol as usize] = true;
+        }
+        Alphabet::Custom(Box::new(set))
+    }
+
+    // "alphabetic", "printable", "any" or "custom:<symbols>"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "alphabetic" => Some(Alphabet::Alphabetic),
+            "printable" => Some(Alphabet::PrintableAscii),
+            "any" => Some(Alphabet::AnyByte),
+            _ => name
+                .strip_prefix("custom:")
+                .map(|symbols| Alphabet::custom(symbols.as_bytes())),
+        }
+    }
+
+    pub fn contains(&self, byte: u8) -> bool {
+        match self {
+            Alphabet::Alphabetic => byte.is_ascii_alphabetic(),
+            Alphabet::PrintableAscii => byte == b' ' || byte.is_ascii_graphic(),
+            Alphabet::AnyByte => true,
+            Alphabet::Custom(set) => set[byte as usize],
+        }
+    }
+}
+
+// What to do with a byte that isn't in the alphabet
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OutOfAlphabet {
+    // Start a new window after it
+    #[default]
+    Reset,
+    // Ignore it, the window continues with the next byte
+    Skip,
+    // Stop the search with `MarkerError::OutOfAlphabet`
+    Error,
+}
+
+impl OutOfAlphabet {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "reset" => Some(OutOfAlphabet::Reset),
+            "skip" => Some(OutOfAlphabet::Skip),
+            "error" => Some(OutOfAlphabet::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MarkerOptions {
+    pub alphabet: Alphabet,
+    pub out_of_alphabet: OutOfAlphabet,
+}
+
+#[derive(Debug)]
+pub enum MarkerError {
+    Io(io::Error),
+    OutOfAlphabet { offset: usize, byte: u8 },
+}
+
+impl fmt::Display for MarkerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MarkerError::Io(error) => write!(f, "read error: {}", error),
+            MarkerError::OutOfAlphabet { offset, byte } => write!(
+                f,
+                "byte {:#04x} at offset {} is not in the alphabet",
+                byte, offset
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for MarkerError {
+    fn from(error: io::Error) -> Self {
+        MarkerError::Io(error)
+    }
+}
+
+// How a byte of the stream is handled by the detector
+#[derive(Debug, PartialEq)]
+pub enum Symbol {
+    Push,
+    Reset,
+    Skip,
+}
+
+impl MarkerOptions {
+    pub fn symbol(&self, offset: usize, byte: u8) -> Result<Symbol, MarkerError> {
+        if self.alphabet.contains(byte) {
+            return Ok(Symbol::Push);
+        }
+        match self.out_of_alphabet {
+            OutOfAlphabet::Reset => Ok(Symbol::Reset),
+            OutOfAlphabet::Skip => Ok(Symbol::Skip),
+            OutOfAlphabet::Error => Err(MarkerError::OutOfAlphabet { offset, byte }),
+        }
+    }
+}
+
+// Call `f` with the offset of every byte of the stream, until it breaks
+pub fn for_each_byte<R: Read>(
+    mut reader: R,
+    mut f: impl FnMut(usize, u8) -> Result<ControlFlow<()>, MarkerError>,
+) -> Result<(), MarkerError> {
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut offset = 0;
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(read) => read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error.into()),
+        };
+        for byte in &buffer[..read] {
+            if f(offset, *byte)?.is_break() {
+                return Ok(());
+            }
+            offset += 1;
+        }
+    }
+}
+
+// Scan a stream for the first `len` distinct bytes of the alphabet in a
+// row, and return the number of bytes read up to the end of the marker.
+pub fn find_marker<R: Read>(
+    reader: R,
+    len: usize,
+    options: &MarkerOptions,
+) -> Result<Option<usize>, MarkerError> {
+    let mut detector = MarkerDetector::new(len);
+    let mut found = None;
+    for_each_byte(reader, |offset, byte| {
+        match options.symbol(offset, byte)? {
+            Symbol::Push => {
+                if detector.push(byte) {
+                    found = Some(offset + 1);
+                    return Ok(ControlFlow::Break(()));
+                }
+            }
+            Symbol::Reset => detector.reset(),
+            Symbol::Skip => {}
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detector() {
+        let mut detector = MarkerDetector::new(3);
+        let found: Vec<bool> = b"abacbbcd".iter().map(|b| detector.push(*b)).collect();
+        assert_eq!(
+            found,
+            vec![false, false, false, true, true, false, false, true]
+        );
+
+        detector.reset();
+        assert!(!detector.push(b'd'));
+        assert!(!detector.push(b'e'));
+        assert!(detector.push(b'f'));
+
+        let mut detector = MarkerDetector::new(1);
+        assert!(detector.push(b'a'));
+        assert!(detector.push(b'a'));
+    }
+
+    // Reader returning a few bytes per call, to cross chunk boundaries
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.0.len().min(buf.len()).min(3);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    fn find(data: &[u8], len: usize) -> Option<usize> {
+        find_marker(data, len, &MarkerOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_find_marker() {
+        let data = b"mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        let options = MarkerOptions::default();
+        assert_eq!(find(data, 4), Some(7));
+        assert_eq!(find_marker(Trickle(data), 4, &options).unwrap(), Some(7));
+        assert_eq!(find_marker(Trickle(data), 14, &options).unwrap(), Some(19));
+        assert_eq!(find(b"abc", 4), None);
+
+        // Longer than the alphabet, and longer than a chunk
+        let long: Vec<u8> = b"abcdefghijklmnopqrstuvwxyz"
+            .iter()
+            .copied()
+            .cycle()
+            .take(CHUNK_SIZE * 2 + 5)
+            .collect();
+        assert_eq!(find(&long, 26), Some(26));
+        assert_eq!(find(&long, 27), None);
+        assert_eq!(find(&long, 1000), None);
+
+        let mut marker_after_chunk = vec![b'a'; CHUNK_SIZE - 1];
+        marker_after_chunk.extend(b"bcd");
+        assert_eq!(find(&marker_after_chunk, 4), Some(CHUNK_SIZE + 2));
+    }
+
+    #[test]
+    fn test_out_of_alphabet() {
+        let find_with = |data: &[u8], out_of_alphabet| {
+            let options = MarkerOptions {
+                out_of_alphabet,
+                ..Default::default()
+            };
+            find_marker(data, 4, &options)
+        };
+
+        assert_eq!(find_with(b"ab(c)d", OutOfAlphabet::Reset).unwrap(), None);
+        assert_eq!(
+            find_with(b"ab(c)defg\n", OutOfAlphabet::Reset).unwrap(),
+            Some(9)
+        );
+        assert_eq!(find_with(b"ab(c)d", OutOfAlphabet::Skip).unwrap(), Some(6));
+        assert_eq!(
+            find_with(b"a\nb\nc\nd\n", OutOfAlphabet::Skip).unwrap(),
+            Some(7)
+        );
+        assert!(matches!(
+            find_with(b"ab(c)d", OutOfAlphabet::Error),
+            Err(MarkerError::OutOfAlphabet {
+                offset: 2,
+                byte: b'('
+            })
+        ));
+        // The marker is found before reaching the newline
+        assert_eq!(find_with(b"abcd\n", OutOfAlphabet::Error).unwrap(), Some(4));
+    }
+
+    #[test]
+    fn test_alphabets() {
+        let find_in = |data: &[u8], len, alphabet| {
+            let options = MarkerOptions {
+                alphabet,
+                out_of_alphabet: OutOfAlphabet::Error,
+            };
+            find_marker(data, len, &options)
+        };
+
+        assert!(find_in(b"a1b2", 4, Alphabet::Alphabetic).is_err());
+        assert_eq!(
+            find_in(b"a1 b2", 4, Alphabet::PrintableAscii).unwrap(),
+            Some(4)
+        );
+        assert!(find_in(b"a1\tb2", 4, Alphabet::PrintableAscii).is_err());
+
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(find_in(&bytes, 256, Alphabet::AnyByte).unwrap(), Some(256));
+        assert_eq!(
+            find_in(&[0, 0, 255, 0], 2, Alphabet::AnyByte).unwrap(),
+            Some(3)
+        );
+
+        let dna = Alphabet::from_name("custom:ACGT").unwrap();
+        assert_eq!(find_in(b"AACGGTCA", 4, dna.clone()).unwrap(), Some(8));
+        assert!(matches!(
+            find_in(b"AACU", 4, dna),
+            Err(MarkerError::OutOfAlphabet { offset: 3, .. })
+        ));
+
+        assert_eq!(Alphabet::from_name("any"), Some(Alphabet::AnyByte));
+        assert_eq!(Alphabet::from_name("digits"), None);
+        assert_eq!(OutOfAlphabet::from_name("skip"), Some(OutOfAlphabet::Skip));
+    }
+}
@@ -0,0 +1,352 @@
+use std::fmt;
+
+use crate::filesystem::{DirId, Directory, Entry, FileSystem};
+
+#[derive(Debug, Clone)]
+pub struct CleanupConstraints {
+    // Paths that must survive the cleanup, with all their content
+    pub protected: Vec<String>,
+    pub max_deletions: Option<usize>,
+    // Number of search steps before settling for the best plan found
+    pub max_nodes: usize,
+}
+
+impl Default for CleanupConstraints {
+    fn default() -> Self {
+        CleanupConstraints {
+            protected: Vec::new(),
+            max_deletions: None,
+            max_nodes: 1_000_000,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CleanupError {
+    UnknownProtectedPath { path: String },
+    // No set of directories frees enough space within the constraints
+    Infeasible,
+    // The search budget ran out before any plan was found
+    LimitReached,
+}
+
+// Directories to remove, none of them inside another one
+#[derive(Debug, PartialEq)]
+pub struct CleanupPlan<'a> {
+    pub dirs: Vec<Directory<'a>>,
+    pub freed: usize,
+    // False when the search budget ran out, a better plan may exist
+    pub optimal: bool,
+}
+
+impl fmt::Display for CleanupPlan<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Remove {} directory(ies) to free {} bytes",
+            self.dirs.len(),
+            self.freed
+        )?;
+        if !self.optimal {
+            writeln!(f, "  (search limit reached, the plan may not be optimal)")?;
+        }
+        for dir in &self.dirs {
+            writeln!(f, "  {}\t{}", dir.recursive_size(), dir.path())?;
+        }
+        Ok(())
+    }
+}
+
+// A directory in preorder, the directories of its subtree follow it
+// up to `end`
+struct Candidate<'a> {
+    dir: Directory<'a>,
+    deletable: bool,
+    end: usize,
+}
+
+fn flatten<'a>(dir: Directory<'a>, protected: &[DirId], out: &mut Vec<Candidate<'a>>) {
+    let fs = dir.filesystem();
+    let deletable = !protected
+        .iter()
+        .any(|p| fs.is_ancestor(dir.id(), *p) || fs.is_ancestor(*p, dir.id()));
+    let idx = out.len();
+    out.push(Candidate {
+        dir,
+        deletable,
+        end: 0,
+    });
+    // Larger directories first, so that tight plans are found early
+    let mut subdirs: Vec<Directory> = dir.subdirs().collect();
+    subdirs.sort_by_key(|subdir| std::cmp::Reverse(subdir.recursive_size()));
+    for subdir in subdirs {
+        flatten(subdir, protected, out);
+    }
+    out[idx].end = out.len();
+}
+
+// Branch and bound over the preorder list: each directory is either
+// removed, skipping its subtree, or kept and its subdirectories are
+// considered one by one
+struct Search<'a, 'c> {
+    candidates: &'c [Candidate<'a>],
+    // Most space that can be freed from the directories after an index
+    reach: Vec<usize>,
+    required: usize,
+    max_deletions: usize,
+    max_nodes: usize,
+    nodes: usize,
+    chosen: Vec<usize>,
+    // Freed space and chosen directories of the best plan found so far
+    best: Option<(usize, Vec<usize>)>,
+}
+
+impl Search<'_, '_> {
+    // Would a plan freeing `freed` bytes with `count` directories be
+    // better than the best one, less bytes first then less directories
+    fn beats(&self, freed: usize, count: usize) -> bool {
+        self.best
+            .as_ref()
+            .is_none_or(|(best, dirs)| (freed, count) < (*best, dirs.len()))
+    }
+
+    // A plan freeing exactly the required space can't be beaten on bytes
+    fn is_done(&self) -> bool {
+        self.nodes > self.max_nodes
+            || self
+                .best
+                .as_ref()
+                .is_some_and(|(freed, _)| *freed == self.required)
+    }
+
+    fn explore(&mut self, idx: usize, freed: usize) {
+        if self.is_done() {
+            return;
+        }
+        self.nodes += 1;
+        if freed >= self.required {
+            if self.beats(freed, self.chosen.len()) {
+                self.best = Some((freed, self.chosen.clone()));
+            }
+            return;
+        }
+        if idx == self.candidates.len()
+            || self.chosen.len() == self.max_deletions
+            || freed + self.reach[idx] < self.required
+        {
+            return;
+        }
+
+        let candidate = &self.candidates[idx];
+        let size = candidate.dir.recursive_size();
+        if candidate.deletable && size > 0 && self.beats(freed + size, self.chosen.len() + 1) {
+            self.chosen.push(idx);
+            self.explore(candidate.end, freed + size);
+            self.chosen.pop();
+        }
+        self.explore(idx + 1, freed);
+    }
+}
+
+// Find the directories to remove to free at least `required` bytes,
+// removing as few bytes as possible, then as few directories. The search
+// stops at the first plan freeing exactly `required` bytes, and after
+// `max_nodes` steps with the best plan found so far.
+pub fn plan_cleanup<'a>(
+    fs: &'a FileSystem,
+    required: usize,
+    constraints: &CleanupConstraints,
+) -> Result<CleanupPlan<'a>, CleanupError> {
+    let protected = constraints
+        .protected
+        .iter()
+        .map(|path| match fs.resolve(FileSystem::ROOT, path) {
+            Some(Entry::Dir(dir)) => Ok(dir.id()),
+            // Removing the directory of a file would remove the file
+            Some(Entry::File(dir, _)) => Ok(dir.id()),
+            None => Err(CleanupError::UnknownProtectedPath { path: path.clone() }),
+        })
+        .collect::<Result<Vec<DirId>, _>>()?;
+
+    let mut candidates = Vec::new();
+    flatten(fs.root(), &protected, &mut candidates);
+
+    let mut reach = vec![0; candidates.len() + 1];
+    for (idx, candidate) in candidates.iter().enumerate().rev() {
+        let removed = if candidate.deletable {
+            candidate.dir.recursive_size() + reach[candidate.end]
+        } else {
+            0
+        };
+        reach[idx] = removed.max(reach[idx + 1]);
+    }
+
+    let mut search = Search {
+        candidates: &candidates,
+        reach,
+        required,
+        max_deletions: constraints.max_deletions.unwrap_or(usize::MAX),
+        max_nodes: constraints.max_nodes,
+        nodes: 0,
+        chosen: Vec::new(),
+        best: None,
+    };
+    search.explore(0, 0);
+
+    let optimal = search.nodes <= search.max_nodes;
+    let (freed, chosen) = match search.best {
+        Some(best) => best,
+        None if optimal => return Err(CleanupError::Infeasible),
+        None => return Err(CleanupError::LimitReached),
+    };
+    let mut dirs: Vec<Directory> = chosen.iter().map(|idx| candidates[*idx].dir).collect();
+    dirs.sort_by_key(|dir| dir.path());
+    Ok(CleanupPlan {
+        dirs,
+        freed,
+        optimal,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // - / (dir)
+    //   - x (dir)
+    //     - x1 (dir, size=40)
+    //     - x2 (dir, size=20)
+    //   - y (dir, size=50)
+    //   - z (dir, size=45)
+    fn example() -> FileSystem {
+        let mut fs = FileSystem::new();
+        let x = fs.add_subdir(FileSystem::ROOT, "x");
+        for (parent, name, size) in [(x, "x1", 40), (x, "x2", 20)] {
+            let dir = fs.add_subdir(parent, name);
+            fs.add_file(dir, "data", size);
+        }
+        for (name, size) in [("y", 50), ("z", 45)] {
+            let dir = fs.add_subdir(FileSystem::ROOT, name);
+            fs.add_file(dir, "data", size);
+        }
+        fs
+    }
+
+    fn paths(plan: &CleanupPlan) -> Vec<String> {
+        plan.dirs.iter().map(|dir| dir.path()).collect()
+    }
+
+    #[test]
+    fn test_plan_cleanup() {
+        let fs = example();
+        let plan = plan_cleanup(&fs, 65, &CleanupConstraints::default()).unwrap();
+        assert_eq!(paths(&plan), vec!["/x/x2", "/z"]);
+        assert_eq!(plan.freed, 65);
+
+        let plan = plan_cleanup(&fs, 95, &CleanupConstraints::default()).unwrap();
+        assert_eq!(paths(&plan), vec!["/y", "/z"]);
+
+        // A single directory is enough, and nested ones aren't counted twice
+        let plan = plan_cleanup(&fs, 55, &CleanupConstraints::default()).unwrap();
+        assert_eq!(paths(&plan), vec!["/x"]);
+        let plan = plan_cleanup(&fs, 150, &CleanupConstraints::default()).unwrap();
+        assert_eq!(paths(&plan), vec!["/"]);
+        assert_eq!(plan.freed, 155);
+
+        assert_eq!(
+            plan_cleanup(&fs, 156, &CleanupConstraints::default()),
+            Err(CleanupError::Infeasible)
+        );
+    }
+
+    #[test]
+    fn test_plan_constraints() {
+        let fs = example();
+        let constraints = CleanupConstraints {
+            max_deletions: Some(1),
+            ..Default::default()
+        };
+        let plan = plan_cleanup(&fs, 65, &constraints).unwrap();
+        assert_eq!(paths(&plan), vec!["/"]);
+
+        let constraints = CleanupConstraints {
+            protected: vec!["/x/x2".to_string()],
+            ..Default::default()
+        };
+        let plan = plan_cleanup(&fs, 65, &constraints).unwrap();
+        assert_eq!(paths(&plan), vec!["/x/x1", "/z"]);
+        assert_eq!(plan.freed, 85);
+
+        let constraints = CleanupConstraints {
+            protected: vec!["z/data".to_string()],
+            max_deletions: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            plan_cleanup(&fs, 65, &constraints),
+            Err(CleanupError::Infeasible)
+        );
+
+        let constraints = CleanupConstraints {
+            protected: vec!["/w".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            plan_cleanup(&fs, 65, &constraints),
+            Err(CleanupError::UnknownProtectedPath {
+                path: "/w".to_string()
+            })
+        );
+    }
+
+    // Sibling directories of distinct sizes, with no subset freeing exactly
+    // the required space, so only the budget ends the search
+    fn many_dirs(count: usize) -> FileSystem {
+        let mut fs = FileSystem::new();
+        for idx in 0..count {
+            let dir = fs.add_subdir(FileSystem::ROOT, &format!("dir{}", idx));
+            fs.add_file(dir, "data", 1000 + idx * 2);
+        }
+        fs
+    }
+
+    #[test]
+    fn test_plan_many_dirs() {
+        let fs = many_dirs(40);
+        let budget = |max_nodes| CleanupConstraints {
+            max_nodes,
+            ..Default::default()
+        };
+        // Every size is even, so the odd requirement is never met exactly
+        // and the search runs until the budget is spent
+        let plan = plan_cleanup(&fs, 20001, &budget(10_000)).unwrap();
+        assert!(!plan.optimal);
+        assert!(plan.freed > 20001);
+        let sizes: usize = plan.dirs.iter().map(|dir| dir.recursive_size()).sum();
+        assert_eq!(sizes, plan.freed);
+
+        // An exact plan ends the search well within a small budget
+        let plan = plan_cleanup(&fs, 1078 + 1076, &budget(100)).unwrap();
+        assert!(plan.optimal);
+        assert_eq!(plan.freed, 2154);
+
+        let constraints = CleanupConstraints {
+            max_nodes: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            plan_cleanup(&fs, 20001, &constraints),
+            Err(CleanupError::LimitReached)
+        );
+    }
+
+    #[test]
+    fn test_plan_display() {
+        let fs = example();
+        let plan = plan_cleanup(&fs, 65, &CleanupConstraints::default()).unwrap();
+        assert_eq!(
+            plan.to_string(),
+            "Remove 2 directory(ies) to free 65 bytes\n  20\t/x/x2\n  45\t/z\n"
+        );
+    }
+}
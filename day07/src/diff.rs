@@ -0,0 +1,264 @@
+use std::fmt;
+
+use crate::export::json_string;
+use crate::filesystem::{Directory, File};
+use crate::query::Kind;
+
+// An entry that differs between two snapshots, the size of a directory
+// is its recursive size so its delta rolls up the changes below it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub path: String,
+    pub kind: Kind,
+    // None when the entry was added
+    pub old_size: Option<usize>,
+    // None when the entry was removed
+    pub new_size: Option<usize>,
+}
+
+impl Change {
+    pub fn delta(&self) -> i64 {
+        self.new_size.unwrap_or(0) as i64 - self.old_size.unwrap_or(0) as i64
+    }
+
+    fn label(&self) -> &'static str {
+        match (self.old_size, self.new_size) {
+            (None, _) => "added",
+            (_, None) => "removed",
+            _ => "resized",
+        }
+    }
+}
+
+// Changes sorted by path, a directory before its content. The content
+// of an added or removed directory isn't listed, only its total size.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diff {
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    // {"changes":[{"path":"/a","kind":"dir","change":"resized","old_size":1,"new_size":2,"delta":1}]}
+    pub fn to_json(&self) -> String {
+        let size = |size: Option<usize>| size.map_or("null".to_string(), |s| s.to_string());
+        let changes: Vec<String> = self
+            .changes
+            .iter()
+            .map(|change| {
+                format!(
+                    "{{\"path\":{},\"kind\":\"{}\",\"change\":\"{}\",\"old_size\":{},\"new_size\":{},\"delta\":{}}}",
+                    json_string(&change.path),
+                    change.kind,
+                    change.label(),
+                    size(change.old_size),
+                    size(change.new_size),
+                    change.delta()
+                )
+            })
+            .collect();
+        format!("{{\"changes\":[{}]}}", changes.join(","))
+    }
+}
+
+// One line per change: `+` added, `-` removed, `~` resized
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in &self.changes {
+            match (change.old_size, change.new_size) {
+                (None, Some(size)) => writeln!(f, "+ {} {} ({})", change.kind, change.path, size)?,
+                (Some(size), None) => writeln!(f, "- {} {} ({})", change.kind, change.path, size)?,
+                (Some(old), Some(new)) => writeln!(
+                    f,
+                    "~ {} {} ({} -> {}, {:+})",
+                    change.kind,
+                    change.path,
+                    old,
+                    new,
+                    change.delta()
+                )?,
+                (None, None) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn find_file<'a>(dir: Option<Directory<'a>>, name: &str) -> Option<&'a File> {
+    dir?.files().iter().find(|f| f.name == name)
+}
+
+fn walk(old: Option<Directory>, new: Option<Directory>, changes: &mut Vec<Change>) {
+    let mut names: Vec<&str> = [old, new]
+        .into_iter()
+        .flatten()
+        .flat_map(|dir| {
+            dir.files()
+                .iter()
+                .map(|f| f.name.as_str())
+                .chain(dir.subdirs().map(|subdir| subdir.name()))
+        })
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+
+    for name in names {
+        let path = new.or(old).unwrap().child_path(name);
+        let old_file = find_file(old, name);
+        let new_file = find_file(new, name);
+        if old_file.map(|f| f.size) != new_file.map(|f| f.size) {
+            changes.push(Change {
+                path: path.clone(),
+                kind: Kind::File,
+                old_size: old_file.map(|f| f.size),
+                new_size: new_file.map(|f| f.size),
+            });
+        }
+
+        let old_dir = old.and_then(|dir| dir.subdir(name));
+        let new_dir = new.and_then(|dir| dir.subdir(name));
+        match (old_dir, new_dir) {
+            (None, None) => {}
+            (Some(old_dir), Some(new_dir)) => compare(old_dir, new_dir, changes),
+            _ => changes.push(Change {
+                path,
+                kind: Kind::Dir,
+                old_size: old_dir.map(|dir| dir.recursive_size()),
+                new_size: new_dir.map(|dir| dir.recursive_size()),
+            }),
+        }
+    }
+}
+
+// Directories present in both snapshots
+fn compare(old: Directory, new: Directory, changes: &mut Vec<Change>) {
+    if old.recursive_size() != new.recursive_size() {
+        changes.push(Change {
+            path: new.path(),
+            kind: Kind::Dir,
+            old_size: Some(old.recursive_size()),
+            new_size: Some(new.recursive_size()),
+        });
+    }
+    walk(Some(old), Some(new), changes);
+}
+
+// Compare two snapshots of the same directory, paths are relative to
+// the new one
+pub fn diff(old: Directory, new: Directory) -> Diff {
+    let mut changes = Vec::new();
+    compare(old, new, &mut changes);
+    Diff { changes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::parse_tree;
+
+    const OLD: &str = "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+  - b.txt (file, size=100)
+  - d (dir)
+    - j (file, size=4000)
+  - x (file, size=5)
+";
+
+    const NEW: &str = "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=600)
+    - f (file, size=29116)
+    - g (file, size=2557)
+  - c (dir)
+    - k (file, size=10)
+  - x (dir)
+    - y (file, size=1)
+";
+
+    #[test]
+    fn test_diff() {
+        let old = parse_tree(OLD).unwrap();
+        let new = parse_tree(NEW).unwrap();
+        let diff = diff(old.root(), new.root());
+        let change = |path: &str, kind, old_size, new_size| Change {
+            path: path.to_string(),
+            kind,
+            old_size,
+            new_size,
+        };
+        assert_eq!(
+            diff.changes,
+            vec![
+                change("/", Kind::Dir, Some(33805), Some(32284)),
+                change("/a", Kind::Dir, Some(29700), Some(32273)),
+                change("/a/e", Kind::Dir, Some(584), Some(600)),
+                change("/a/e/i", Kind::File, Some(584), Some(600)),
+                change("/a/g", Kind::File, None, Some(2557)),
+                change("/b.txt", Kind::File, Some(100), None),
+                change("/c", Kind::Dir, None, Some(10)),
+                change("/d", Kind::Dir, Some(4000), None),
+                change("/x", Kind::File, Some(5), None),
+                change("/x", Kind::Dir, None, Some(1)),
+            ]
+        );
+        assert_eq!(diff.changes[0].delta(), -1521);
+        assert_eq!(diff.changes[1].delta(), 2573);
+
+        assert!(super::diff(old.root(), old.root()).is_empty());
+        // Only the resized directories differ once sizes are rolled up
+        let a = super::diff(
+            old.root().subdir("a").unwrap(),
+            new.root().subdir("a").unwrap(),
+        );
+        assert_eq!(a.changes.len(), 4);
+    }
+
+    #[test]
+    fn test_diff_output() {
+        let old = parse_tree(OLD).unwrap();
+        let new = parse_tree(NEW).unwrap();
+        let diff = diff(
+            old.root().subdir("a").unwrap(),
+            new.root().subdir("a").unwrap(),
+        );
+        assert_eq!(
+            diff.to_string(),
+            "~ dir /a (29700 -> 32273, +2573)
+~ dir /a/e (584 -> 600, +16)
+~ file /a/e/i (584 -> 600, +16)
++ file /a/g (2557)
+"
+        );
+
+        let old = parse_tree("- / (dir)\n  - b (file, size=3)\n").unwrap();
+        let new = parse_tree("- / (dir)\n").unwrap();
+        assert_eq!(
+            super::diff(old.root(), new.root()).to_json(),
+            "{\"changes\":[\
+{\"path\":\"/\",\"kind\":\"dir\",\"change\":\"resized\",\"old_size\":3,\"new_size\":0,\"delta\":-3},\
+{\"path\":\"/b\",\"kind\":\"file\",\"change\":\"removed\",\"old_size\":3,\"new_size\":null,\"delta\":-3}]}"
+        );
+        assert_eq!(Diff::default().to_json(), "{\"changes\":[]}");
+    }
+
+    #[test]
+    fn test_diff_moved_dir() {
+        let old =
+            parse_tree("- / (dir)\n  - a (dir)\n    - f (file, size=1)\n    - e (dir)\n").unwrap();
+        let new =
+            parse_tree("- / (dir)\n  - b (dir)\n    - f (file, size=2)\n    - g (dir)\n").unwrap();
+        let diff = diff(
+            old.root().subdir("a").unwrap(),
+            new.root().subdir("b").unwrap(),
+        );
+        let paths: Vec<&str> = diff.changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, ["/b", "/b/e", "/b/f", "/b/g"]);
+    }
+}
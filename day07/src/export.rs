@@ -0,0 +1,316 @@
+use std::fmt;
+
+use crate::filesystem::{DirId, Directory, FileSystem};
+
+// Entries of a directory sorted by name, subdirectories with their
+// directory and files with their size
+fn sorted_entries<'a>(dir: Directory<'a>) -> Vec<(&'a str, Option<Directory<'a>>, usize)> {
+    let mut entries: Vec<(&str, Option<Directory>, usize)> = dir
+        .subdirs()
+        .map(|subdir| (subdir.name(), Some(subdir), subdir.recursive_size()))
+        .chain(dir.files().iter().map(|f| (f.name.as_str(), None, f.size)))
+        .collect();
+    entries.sort_by_key(|(name, _, _)| *name);
+    entries
+}
+
+// Indented listing like the puzzle statement, entries sorted by name
+pub fn to_tree(dir: Directory) -> String {
+    fn walk(dir: Directory, depth: usize, output: &mut String) {
+        *output += &format!("{}- {} (dir)\n", "  ".repeat(depth), dir.name());
+        for (name, subdir, size) in sorted_entries(dir) {
+            match subdir {
+                Some(subdir) => walk(subdir, depth + 1, output),
+                None => {
+                    *output += &format!(
+                        "{}- {} (file, size={})\n",
+                        "  ".repeat(depth + 1),
+                        name,
+                        size
+                    )
+                }
+            }
+        }
+    }
+
+    let mut output = String::new();
+    walk(dir, 0, &mut output);
+    output
+}
+
+// Recursive size and path of every directory, largest first
+pub fn to_du(dir: Directory) -> String {
+    fn walk<'a>(dir: Directory<'a>, dirs: &mut Vec<Directory<'a>>) {
+        dirs.push(dir);
+        for subdir in dir.subdirs() {
+            walk(subdir, dirs);
+        }
+    }
+
+    let mut dirs = Vec::new();
+    walk(dir, &mut dirs);
+    dirs.sort_by_cached_key(|dir| (std::cmp::Reverse(dir.recursive_size()), dir.path()));
+    dirs.iter()
+        .map(|dir| format!("{}\t{}\n", dir.recursive_size(), dir.path()))
+        .collect()
+}
+
+// JSON string literal, quoted and escaped
+pub fn json_string(s: &str) -> String {
+    let mut output = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => output += "\\\"",
+            '\\' => output += "\\\\",
+            '\n' => output += "\\n",
+            '\t' => output += "\\t",
+            c if (c as u32) < 0x20 => output += &format!("\\u{:04x}", c as u32),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+// JSON object of the directory, its size is the recursive size and its
+// entries are sorted by name:
+// {"name":"e","type":"dir","size":584,"entries":[{"name":"i","type":"file","size":584}]}
+pub fn to_json(dir: Directory) -> String {
+    fn walk(dir: Directory, output: &mut String) {
+        *output += &format!(
+            "{{\"name\":{},\"type\":\"dir\",\"size\":{},\"entries\":[",
+            json_string(dir.name()),
+            dir.recursive_size()
+        );
+        for (idx, (name, subdir, size)) in sorted_entries(dir).into_iter().enumerate() {
+            if idx > 0 {
+                output.push(',');
+            }
+            match subdir {
+                Some(subdir) => walk(subdir, output),
+                None => {
+                    *output += &format!(
+                        "{{\"name\":{},\"type\":\"file\",\"size\":{}}}",
+                        json_string(name),
+                        size
+                    )
+                }
+            }
+        }
+        *output += "]}";
+    }
+
+    let mut output = String::new();
+    walk(dir, &mut output);
+    output
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TreeError {
+    // Not `- name (dir)` or `- name (file, size=N)`
+    InvalidLine { line: usize },
+    // Odd indentation, or deeper than a child of the previous directory
+    InvalidIndent { line: usize },
+    InvalidSize { line: usize, size: String },
+    // The first line isn't a directory without indentation, or another
+    // line has no indentation
+    InvalidRoot { line: usize },
+    Duplicate { line: usize, name: String },
+}
+
+impl fmt::Display for TreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TreeError::InvalidLine { line } => write!(f, "line {}: invalid entry", line),
+            TreeError::InvalidIndent { line } => write!(f, "line {}: invalid indentation", line),
+            TreeError::InvalidSize { line, size } => {
+                write!(f, "line {}: invalid size {}", line, size)
+            }
+            TreeError::InvalidRoot { line } => write!(f, "line {}: expected a single root", line),
+            TreeError::Duplicate { line, name } => {
+                write!(f, "line {}: {} is listed twice", line, name)
+            }
+        }
+    }
+}
+
+// Build a filesystem from the output of `to_tree`, the top directory
+// becomes the root whatever its name. Blank lines are ignored.
+pub fn parse_tree(text: &str) -> Result<FileSystem, TreeError> {
+    let mut fs = FileSystem::new();
+    // Directories from the root to the last one listed
+    let mut stack: Vec<DirId> = Vec::new();
+    let mut has_root = false;
+
+    for (idx, raw_line) in text.lines().enumerate() {
+        let line = idx + 1;
+        if raw_line.trim().is_empty() {
+            continue;
+        }
+        let content = raw_line.trim_start_matches(' ');
+        let indent = raw_line.len() - content.len();
+        let (name, kind) = content
+            .strip_prefix("- ")
+            .and_then(|entry| entry.strip_suffix(')'))
+            .and_then(|entry| entry.rsplit_once(" ("))
+            .ok_or(TreeError::InvalidLine { line })?;
+        if name.is_empty() {
+            return Err(TreeError::InvalidLine { line });
+        }
+        if indent % 2 != 0 {
+            return Err(TreeError::InvalidIndent { line });
+        }
+
+        let depth = indent / 2;
+        if depth == 0 {
+            if has_root || kind != "dir" {
+                return Err(TreeError::InvalidRoot { line });
+            }
+            has_root = true;
+            stack.push(FileSystem::ROOT);
+            continue;
+        }
+        if !has_root {
+            return Err(TreeError::InvalidRoot { line });
+        }
+        if depth > stack.len() {
+            return Err(TreeError::InvalidIndent { line });
+        }
+        stack.truncate(depth);
+        let parent = stack[depth - 1];
+        let dir = fs.dir(parent);
+        if dir.subdir(name).is_some() || dir.files().iter().any(|f| f.name == name) {
+            return Err(TreeError::Duplicate {
+                line,
+                name: name.to_string(),
+            });
+        }
+
+        if kind == "dir" {
+            stack.push(fs.add_subdir(parent, name));
+        } else {
+            let size = kind
+                .strip_prefix("file, size=")
+                .ok_or(TreeError::InvalidLine { line })?;
+            let size = size.parse().map_err(|_| TreeError::InvalidSize {
+                line,
+                size: size.to_string(),
+            })?;
+            fs.add_file(parent, name, size);
+        }
+    }
+    Ok(fs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_TREE: &str = "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+";
+
+    #[test]
+    fn test_tree_round_trip() {
+        let fs = parse_tree(EXAMPLE_TREE).unwrap();
+        assert_eq!(fs.root().recursive_size(), 48381165);
+        assert_eq!(to_tree(fs.root()), EXAMPLE_TREE);
+        assert_eq!(
+            to_tree(fs.root().subdir("a").unwrap().subdir("e").unwrap()),
+            "- e (dir)\n  - i (file, size=584)\n"
+        );
+
+        // Entries are sorted on export, and the root name is ignored
+        let shuffled = parse_tree("- root (dir)\n  - z (file, size=1)\n  - y (dir)\n").unwrap();
+        assert_eq!(
+            to_tree(shuffled.root()),
+            "- / (dir)\n  - y (dir)\n  - z (file, size=1)\n"
+        );
+        assert_eq!(parse_tree("").unwrap(), FileSystem::new());
+    }
+
+    #[test]
+    fn test_parse_tree_errors() {
+        assert_eq!(
+            parse_tree("- / (dir)\n  - a (directory)\n"),
+            Err(TreeError::InvalidLine { line: 2 })
+        );
+        assert_eq!(
+            parse_tree("- / (dir)\n  a (dir)\n"),
+            Err(TreeError::InvalidLine { line: 2 })
+        );
+        assert_eq!(
+            parse_tree("- / (dir)\n   - a (dir)\n"),
+            Err(TreeError::InvalidIndent { line: 2 })
+        );
+        assert_eq!(
+            parse_tree("- / (dir)\n  - a (file, size=1)\n    - b (dir)\n"),
+            Err(TreeError::InvalidIndent { line: 3 })
+        );
+        assert_eq!(
+            parse_tree("- / (dir)\n  - a (file, size=1k)\n"),
+            Err(TreeError::InvalidSize {
+                line: 2,
+                size: "1k".to_string()
+            })
+        );
+        assert_eq!(
+            parse_tree("  - a (dir)\n"),
+            Err(TreeError::InvalidRoot { line: 1 })
+        );
+        assert_eq!(
+            parse_tree("- / (dir)\n- b (dir)\n"),
+            Err(TreeError::InvalidRoot { line: 2 })
+        );
+        assert_eq!(
+            parse_tree("- / (dir)\n  - a (dir)\n  - a (file, size=1)\n"),
+            Err(TreeError::Duplicate {
+                line: 3,
+                name: "a".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_to_du() {
+        let fs = parse_tree(EXAMPLE_TREE).unwrap();
+        assert_eq!(
+            to_du(fs.root()),
+            "48381165\t/\n24933642\t/d\n94853\t/a\n584\t/a/e\n"
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let fs = parse_tree(EXAMPLE_TREE).unwrap();
+        assert_eq!(
+            to_json(fs.root().subdir("a").unwrap()),
+            "{\"name\":\"a\",\"type\":\"dir\",\"size\":94853,\"entries\":[\
+{\"name\":\"e\",\"type\":\"dir\",\"size\":584,\"entries\":[\
+{\"name\":\"i\",\"type\":\"file\",\"size\":584}]},\
+{\"name\":\"f\",\"type\":\"file\",\"size\":29116},\
+{\"name\":\"g\",\"type\":\"file\",\"size\":2557},\
+{\"name\":\"h.lst\",\"type\":\"file\",\"size\":62596}]}"
+        );
+
+        let mut fs = FileSystem::new();
+        fs.add_file(FileSystem::ROOT, "say \"hi\"\\\n", 1);
+        assert_eq!(
+            to_json(fs.root()),
+            "{\"name\":\"/\",\"type\":\"dir\",\"size\":1,\"entries\":[\
+{\"name\":\"say \\\"hi\\\"\\\\\\n\",\"type\":\"file\",\"size\":1}]}"
+        );
+    }
+}
@@ -0,0 +1,464 @@
+// Index of a directory in the `FileSystem` arena
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DirId(usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct File {
+    pub name: String,
+    pub size: usize,
+}
+
+// What a path leads to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Entry<'a> {
+    Dir(Directory<'a>),
+    // A file and the directory containing it
+    File(Directory<'a>, &'a File),
+}
+
+#[derive(Debug, Clone)]
+struct DirNode {
+    name: String,
+    parent: Option<DirId>,
+    subdirs: Vec<DirId>,
+    files: Vec<File>,
+    // Size of the whole subtree, updated every time a file is added
+    recursive_size: usize,
+}
+
+// Directories are stored in a flat arena and refer to each other by
+// index, so moving to the parent directory and reading the recursive
+// size of any directory are O(1).
+#[derive(Debug, Clone)]
+pub struct FileSystem {
+    dirs: Vec<DirNode>,
+    // Current directory, used by `cd`
+    cwd: DirId,
+}
+
+impl FileSystem {
+    pub const ROOT: DirId = DirId(0);
+
+    pub fn new() -> Self {
+        FileSystem {
+            dirs: vec![DirNode {
+                name: String::from("/"),
+                parent: None,
+                subdirs: Vec::new(),
+                files: Vec::new(),
+                recursive_size: 0,
+            }],
+            cwd: FileSystem::ROOT,
+        }
+    }
+
+    pub fn root(&self) -> Directory<'_> {
+        self.dir(FileSystem::ROOT)
+    }
+
+    pub fn dir(&self, id: DirId) -> Directory<'_> {
+        Directory { fs: self, id }
+    }
+
+    pub fn cwd(&self) -> DirId {
+        self.cwd
+    }
+
+    pub fn set_cwd(&mut self, dir: DirId) {
+        self.cwd = dir;
+    }
+
+    // Move the cursor to the root directory
+    pub fn cd_root(&mut self) {
+        self.cwd = FileSystem::ROOT;
+    }
+
+    // Move the cursor to the parent directory, the root is its own parent
+    pub fn cd_parent(&mut self) {
+        if let Some(parent) = self.dirs[self.cwd.0].parent {
+            self.cwd = parent;
+        }
+    }
+
+    // Move the cursor to a subdirectory of the current directory
+    pub fn cd(&mut self, name: &str) -> Option<DirId> {
+        self.cwd = self.dir(self.cwd).subdir(name)?.id;
+        Some(self.cwd)
+    }
+
+    // Create a subdirectory, or return the existing one with that name
+    pub fn add_subdir(&mut self, parent: DirId, name: &str) -> DirId {
+        if let Some(subdir) = self.dir(parent).subdir(name) {
+            return subdir.id;
+        }
+        let id = DirId(self.dirs.len());
+        self.dirs.push(DirNode {
+            name: name.to_string(),
+            parent: Some(parent),
+            subdirs: Vec::new(),
+            files: Vec::new(),
+            recursive_size: 0,
+        });
+        self.dirs[parent.0].subdirs.push(id);
+        id
+    }
+
+    // Add a file unless one with that name already exists, and add its
+    // size to the recursive size of the directory and all its ancestors
+    pub fn add_file(&mut self, dir: DirId, name: &str, size: usize) {
+        if self.dirs[dir.0].files.iter().any(|f| f.name == name) {
+            return;
+        }
+        self.dirs[dir.0].files.push(File {
+            name: name.to_string(),
+            size,
+        });
+        self.add_to_ancestors(dir, size);
+    }
+
+    pub fn remove_file(&mut self, dir: DirId, name: &str) -> Option<File> {
+        let files = &mut self.dirs[dir.0].files;
+        let file = files.remove(files.iter().position(|f| f.name == name)?);
+        self.remove_from_ancestors(dir, file.size);
+        Some(file)
+    }
+
+    // Move a file to another directory, or rename it when `to` is `from`.
+    // The file can't replace an existing file or directory.
+    pub fn move_file(&mut self, from: DirId, name: &str, to: DirId, new_name: &str) -> bool {
+        if self.dir(to).has_entry(new_name) {
+            return false;
+        }
+        let Some(file) = self.remove_file(from, name) else {
+            return false;
+        };
+        self.add_file(to, new_name, file.size);
+        true
+    }
+
+    // Detach a directory and its content from the tree. The root can't
+    // be removed, and the cursor moves out of the removed directory.
+    pub fn remove_dir(&mut self, dir: DirId) -> bool {
+        let Some(parent) = self.dirs[dir.0].parent else {
+            return false;
+        };
+        self.dirs[parent.0].subdirs.retain(|id| *id != dir);
+        self.remove_from_ancestors(parent, self.dirs[dir.0].recursive_size);
+        if self.is_ancestor(dir, self.cwd) {
+            self.cwd = parent;
+        }
+        true
+    }
+
+    // Move a directory under `to` as `new_name`. A directory can't be
+    // moved inside itself, nor replace an existing file or directory.
+    pub fn move_dir(&mut self, dir: DirId, to: DirId, new_name: &str) -> bool {
+        let Some(parent) = self.dirs[dir.0].parent else {
+            return false;
+        };
+        if self.is_ancestor(dir, to) || self.dir(to).has_entry(new_name) {
+            return false;
+        }
+        let size = self.dirs[dir.0].recursive_size;
+        self.dirs[parent.0].subdirs.retain(|id| *id != dir);
+        self.remove_from_ancestors(parent, size);
+
+        self.dirs[to.0].subdirs.push(dir);
+        self.add_to_ancestors(to, size);
+        let node = &mut self.dirs[dir.0];
+        node.parent = Some(to);
+        node.name = new_name.to_string();
+        true
+    }
+
+    // True if `ancestor` is `dir` or one of its parents
+    pub fn is_ancestor(&self, ancestor: DirId, dir: DirId) -> bool {
+        let mut current = Some(dir);
+        while let Some(id) = current {
+            if id == ancestor {
+                return true;
+            }
+            current = self.dirs[id.0].parent;
+        }
+        false
+    }
+
+    // Follow a path from `from`, or from the root when it starts with
+    // '/'. "." and ".." are supported, the parent of the root is itself.
+    pub fn resolve(&self, from: DirId, path: &str) -> Option<Entry<'_>> {
+        let mut dir = if path.starts_with('/') {
+            self.root()
+        } else {
+            self.dir(from)
+        };
+        let mut components = path.split('/').filter(|c| !c.is_empty()).peekable();
+        while let Some(component) = components.next() {
+            match component {
+                "." => {}
+                ".." => dir = dir.parent().unwrap_or(dir),
+                name => match dir.subdir(name) {
+                    Some(subdir) => dir = subdir,
+                    None if components.peek().is_none() => {
+                        let file = dir.files().iter().find(|f| f.name == name)?;
+                        return Some(Entry::File(dir, file));
+                    }
+                    None => return None,
+                },
+            }
+        }
+        Some(Entry::Dir(dir))
+    }
+
+    fn add_to_ancestors(&mut self, dir: DirId, size: usize) {
+        let mut ancestor = Some(dir);
+        while let Some(id) = ancestor {
+            self.dirs[id.0].recursive_size += size;
+            ancestor = self.dirs[id.0].parent;
+        }
+    }
+
+    fn remove_from_ancestors(&mut self, dir: DirId, size: usize) {
+        let mut ancestor = Some(dir);
+        while let Some(id) = ancestor {
+            self.dirs[id.0].recursive_size -= size;
+            ancestor = self.dirs[id.0].parent;
+        }
+    }
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        FileSystem::new()
+    }
+}
+
+// Two filesystems are equal when their trees are, whatever the order
+// entries were created in and the current directory
+impl PartialEq for FileSystem {
+    fn eq(&self, other: &Self) -> bool {
+        self.root() == other.root()
+    }
+}
+
+// Read only view of a directory of a `FileSystem`
+#[derive(Debug, Clone, Copy)]
+pub struct Directory<'a> {
+    fs: &'a FileSystem,
+    id: DirId,
+}
+
+impl<'a> Directory<'a> {
+    fn node(&self) -> &'a DirNode {
+        &self.fs.dirs[self.id.0]
+    }
+
+    pub fn id(&self) -> DirId {
+        self.id
+    }
+
+    pub fn filesystem(&self) -> &'a FileSystem {
+        self.fs
+    }
+
+    pub fn name(&self) -> &'a str {
+        &self.node().name
+    }
+
+    pub fn parent(&self) -> Option<Directory<'a>> {
+        Some(self.fs.dir(self.node().parent?))
+    }
+
+    // Absolute path, "/" for the root
+    pub fn path(&self) -> String {
+        match self.parent() {
+            None => String::from("/"),
+            Some(parent) if parent.parent().is_none() => format!("/{}", self.name()),
+            Some(parent) => format!("{}/{}", parent.path(), self.name()),
+        }
+    }
+
+    // Absolute path of an entry of this directory
+    pub fn child_path(&self, name: &str) -> String {
+        match self.parent() {
+            None => format!("/{}", name),
+            Some(_) => format!("{}/{}", self.path(), name),
+        }
+    }
+
+    pub fn files(&self) -> &'a [File] {
+        &self.node().files
+    }
+
+    pub fn subdirs(&self) -> impl Iterator<Item = Directory<'a>> + 'a {
+        let fs = self.fs;
+        self.node().subdirs.iter().map(move |id| fs.dir(*id))
+    }
+
+    pub fn subdir(&self, name: &str) -> Option<Directory<'a>> {
+        self.subdirs().find(|d| d.name() == name)
+    }
+
+    // True if a file or a subdirectory is named `name`
+    pub fn has_entry(&self, name: &str) -> bool {
+        self.subdir(name).is_some() || self.files().iter().any(|f| f.name == name)
+    }
+
+    // Returns size of all files in this directory
+    // And the size of all subdirectories
+    pub fn recursive_size(&self) -> usize {
+        self.node().recursive_size
+    }
+}
+
+// Entries are compared by name, names are unique within a directory
+impl PartialEq for Directory<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+            && self.files().len() == other.files().len()
+            && self.files().iter().all(|f| other.files().contains(f))
+            && self.subdirs().count() == other.subdirs().count()
+            && self
+                .subdirs()
+                .all(|subdir| other.subdir(subdir.name()) == Some(subdir))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // Follow a path of subdirectory names
+    pub(crate) fn get_dir<'a>(dir: Directory<'a>, path: &[&str]) -> Option<Directory<'a>> {
+        match path.split_first() {
+            None => Some(dir),
+            Some((name, rest)) => get_dir(dir.subdir(name)?, rest),
+        }
+    }
+
+    // Returns size of all file at current directory level
+    // (not recursive)
+    pub(crate) fn size(dir: Directory) -> usize {
+        dir.files().iter().map(|f| f.size).sum()
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut fs = FileSystem::new();
+        let a = fs.add_subdir(FileSystem::ROOT, "a");
+        let b = fs.add_subdir(a, "b");
+        assert_eq!(fs.add_subdir(a, "b"), b);
+
+        assert_eq!(fs.cd("a"), Some(a));
+        assert_eq!(fs.cd("b"), Some(b));
+        assert_eq!(fs.cd("c"), None);
+        assert_eq!(fs.cwd(), b);
+        fs.cd_parent();
+        assert_eq!(fs.cwd(), a);
+        fs.cd_root();
+        fs.cd_parent();
+        assert_eq!(fs.cwd(), FileSystem::ROOT);
+    }
+
+    #[test]
+    fn test_incremental_sizes() {
+        let mut fs = FileSystem::new();
+        let a = fs.add_subdir(FileSystem::ROOT, "a");
+        let b = fs.add_subdir(a, "b");
+        let c = fs.add_subdir(FileSystem::ROOT, "c");
+        fs.add_file(b, "x", 10);
+        fs.add_file(a, "y", 5);
+        fs.add_file(c, "z", 1);
+        // Listing a file twice doesn't count it twice
+        fs.add_file(b, "x", 10);
+
+        assert_eq!(fs.dir(b).recursive_size(), 10);
+        assert_eq!(fs.dir(a).recursive_size(), 15);
+        assert_eq!(fs.dir(c).recursive_size(), 1);
+        assert_eq!(fs.root().recursive_size(), 16);
+    }
+
+    #[test]
+    fn test_eq_ignores_creation_order() {
+        let mut first = FileSystem::new();
+        let a = first.add_subdir(FileSystem::ROOT, "a");
+        first.add_file(a, "x", 1);
+        first.add_subdir(FileSystem::ROOT, "b");
+
+        let mut second = FileSystem::new();
+        second.add_subdir(FileSystem::ROOT, "a");
+        second.add_subdir(FileSystem::ROOT, "b");
+        second.cd("a");
+        second.add_file(second.cwd(), "x", 1);
+        assert_eq!(first, second);
+
+        second.add_file(FileSystem::ROOT, "y", 1);
+        assert_ne!(first, second);
+
+        // Siblings listed in another order
+        let mut third = FileSystem::new();
+        third.add_subdir(FileSystem::ROOT, "b");
+        let a = third.add_subdir(FileSystem::ROOT, "a");
+        third.add_file(a, "x", 1);
+        assert_eq!(first, third);
+        first.add_file(FileSystem::ROOT, "y", 1);
+        first.add_file(FileSystem::ROOT, "z", 2);
+        third.add_file(FileSystem::ROOT, "z", 2);
+        third.add_file(FileSystem::ROOT, "y", 1);
+        assert_eq!(first, third);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut fs = FileSystem::new();
+        let a = fs.add_subdir(FileSystem::ROOT, "a");
+        let b = fs.add_subdir(a, "b");
+        fs.add_file(b, "x", 10);
+
+        assert_eq!(fs.dir(b).path(), "/a/b");
+        assert_eq!(fs.root().path(), "/");
+        assert_eq!(fs.resolve(b, "/"), Some(Entry::Dir(fs.root())));
+        assert_eq!(fs.resolve(b, "../.."), Some(Entry::Dir(fs.root())));
+        assert_eq!(
+            fs.resolve(FileSystem::ROOT, ".."),
+            Some(Entry::Dir(fs.root()))
+        );
+        assert_eq!(
+            fs.resolve(FileSystem::ROOT, "a/./b/"),
+            Some(Entry::Dir(fs.dir(b)))
+        );
+        assert_eq!(
+            fs.resolve(a, "b/x"),
+            Some(Entry::File(fs.dir(b), &fs.dir(b).files()[0]))
+        );
+        assert_eq!(fs.resolve(a, "b/x/y"), None);
+        assert_eq!(fs.resolve(a, "c"), None);
+    }
+
+    #[test]
+    fn test_remove_and_move() {
+        let mut fs = FileSystem::new();
+        let a = fs.add_subdir(FileSystem::ROOT, "a");
+        let b = fs.add_subdir(a, "b");
+        let c = fs.add_subdir(FileSystem::ROOT, "c");
+        fs.add_file(b, "x", 10);
+        fs.add_file(a, "y", 5);
+
+        assert!(fs.move_dir(b, c, "b2"));
+        assert_eq!(fs.dir(a).recursive_size(), 5);
+        assert_eq!(fs.dir(c).recursive_size(), 10);
+        assert_eq!(fs.dir(b).path(), "/c/b2");
+        assert!(!fs.move_dir(c, b, "c"));
+        assert!(!fs.move_dir(FileSystem::ROOT, c, "root"));
+
+        assert!(fs.move_file(a, "y", b, "y2"));
+        assert!(!fs.move_file(a, "y", b, "y3"));
+        assert_eq!(fs.dir(c).recursive_size(), 15);
+
+        fs.set_cwd(b);
+        assert!(fs.remove_dir(c));
+        assert_eq!(fs.cwd(), FileSystem::ROOT);
+        assert_eq!(fs.root().recursive_size(), 0);
+        assert!(!fs.remove_dir(FileSystem::ROOT));
+        assert_eq!(fs.remove_file(a, "y"), None);
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::filesystem::{DirId, FileSystem};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Symlinks {
+    // Ignore symbolic links, their targets are counted where they live
+    #[default]
+    Skip,
+    // Import the target of symbolic links, a directory reached twice
+    // is only imported the first time
+    Follow,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    pub symlinks: Symlinks,
+    // Directories deeper than this aren't created, their files are
+    // added to their ancestor at the limit, named by their relative
+    // path, so recursive sizes stay exact
+    pub max_depth: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct Import {
+    pub fs: FileSystem,
+    // Entries that couldn't be read, like broken links or directories
+    // without permission
+    pub skipped: Vec<(PathBuf, io::Error)>,
+}
+
+struct Importer<'a> {
+    options: &'a ImportOptions,
+    fs: FileSystem,
+    skipped: Vec<(PathBuf, io::Error)>,
+    // Canonical paths of the imported directories, to stop on symlink loops
+    visited: HashSet<PathBuf>,
+}
+
+impl Importer<'_> {
+    // Add the content of `path` to `dir`, `prefix` is the path from
+    // `dir` to `path` once past the depth limit
+    fn walk(&mut self, path: &Path, dir: DirId, depth: usize, prefix: &str) {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(error) => return self.skipped.push((path.to_path_buf(), error)),
+        };
+        let mut entries: Vec<fs::DirEntry> = entries
+            .filter_map(|entry| match entry {
+                Ok(entry) => Some(entry),
+                Err(error) => {
+                    self.skipped.push((path.to_path_buf(), error));
+                    None
+                }
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let entry_path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let metadata = match self.metadata(&entry_path) {
+                Ok(Some(metadata)) => metadata,
+                Ok(None) => continue,
+                Err(error) => {
+                    self.skipped.push((entry_path, error));
+                    continue;
+                }
+            };
+
+            if !metadata.is_dir() {
+                let name = format!("{}{}", prefix, name);
+                self.fs.add_file(dir, &name, metadata.len() as usize);
+                continue;
+            }
+            if self.options.symlinks == Symlinks::Follow {
+                match fs::canonicalize(&entry_path) {
+                    Ok(canonical) => {
+                        if !self.visited.insert(canonical) {
+                            continue;
+                        }
+                    }
+                    Err(error) => {
+                        self.skipped.push((entry_path, error));
+                        continue;
+                    }
+                }
+            }
+            let below_limit = self.options.max_depth.is_none_or(|max| depth < max);
+            if prefix.is_empty() && below_limit {
+                let subdir = self.fs.add_subdir(dir, &name);
+                self.walk(&entry_path, subdir, depth + 1, "");
+            } else {
+                let prefix = format!("{}{}/", prefix, name);
+                self.walk(&entry_path, dir, depth + 1, &prefix);
+            }
+        }
+    }
+
+    // Metadata of the entry, or of the link target when following
+    // links, None for a skipped link
+    fn metadata(&self, path: &Path) -> io::Result<Option<fs::Metadata>> {
+        let metadata = fs::symlink_metadata(path)?;
+        if !metadata.file_type().is_symlink() {
+            return Ok(Some(metadata));
+        }
+        match self.options.symlinks {
+            Symlinks::Skip => Ok(None),
+            Symlinks::Follow => fs::metadata(path).map(Some),
+        }
+    }
+}
+
+// Import a local directory and everything below it, the root of the
+// filesystem is `path` itself
+pub fn import_dir(path: &Path, options: &ImportOptions) -> io::Result<Import> {
+    let root = fs::canonicalize(path)?;
+    if !fs::metadata(&root)?.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a directory", path.display()),
+        ));
+    }
+
+    let mut importer = Importer {
+        options,
+        fs: FileSystem::new(),
+        skipped: Vec::new(),
+        visited: HashSet::from([root.clone()]),
+    };
+    importer.walk(&root, FileSystem::ROOT, 0, "");
+    Ok(Import {
+        fs: importer.fs,
+        skipped: importer.skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Temporary directory removed at the end of the test
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("day07-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        fn file(&self, path: &str, size: u64) {
+            let path = self.0.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::File::create(path).unwrap().set_len(size).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn example_tree(name: &str) -> TempDir {
+        let dir = TempDir::new(name);
+        dir.file("a/e/i", 584);
+        dir.file("a/f", 29116);
+        dir.file("a/g", 2557);
+        dir.file("b.txt", 1000);
+        dir.file("d/j", 4000);
+        fs::create_dir(dir.0.join("empty")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = example_tree("import");
+        let import = import_dir(&dir.0, &ImportOptions::default()).unwrap();
+        assert!(import.skipped.is_empty());
+
+        let root = import.fs.root();
+        assert_eq!(root.recursive_size(), 584 + 29116 + 2557 + 1000 + 4000);
+        let a = root.subdir("a").unwrap();
+        assert_eq!(a.recursive_size(), 584 + 29116 + 2557);
+        assert_eq!(a.subdir("e").unwrap().files()[0].name, "i");
+        assert_eq!(root.subdir("empty").unwrap().recursive_size(), 0);
+        let names: Vec<&str> = root.subdirs().map(|d| d.name()).collect();
+        assert_eq!(names, vec!["a", "d", "empty"]);
+
+        assert!(import_dir(&dir.0.join("b.txt"), &ImportOptions::default()).is_err());
+        assert!(import_dir(&dir.0.join("missing"), &ImportOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_import_max_depth() {
+        let dir = example_tree("max-depth");
+        let options = ImportOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let import = import_dir(&dir.0, &options).unwrap();
+        let a = import.fs.root().subdir("a").unwrap();
+        assert_eq!(a.subdirs().count(), 0);
+        let names: Vec<&str> = a.files().iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["e/i", "f", "g"]);
+        assert_eq!(a.recursive_size(), 584 + 29116 + 2557);
+
+        let options = ImportOptions {
+            max_depth: Some(0),
+            ..Default::default()
+        };
+        let import = import_dir(&dir.0, &options).unwrap();
+        assert_eq!(import.fs.root().subdirs().count(), 0);
+        assert_eq!(import.fs.root().files().len(), 5);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_import_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = example_tree("symlinks");
+        symlink(dir.0.join("a"), dir.0.join("d/link-to-a")).unwrap();
+        symlink(dir.0.join("b.txt"), dir.0.join("d/link-to-b")).unwrap();
+        symlink(&dir.0, dir.0.join("a/loop")).unwrap();
+        symlink(dir.0.join("missing"), dir.0.join("broken")).unwrap();
+
+        let import = import_dir(&dir.0, &ImportOptions::default()).unwrap();
+        assert!(import.skipped.is_empty());
+        assert_eq!(import.fs.root().recursive_size(), 37257);
+
+        let options = ImportOptions {
+            symlinks: Symlinks::Follow,
+            ..Default::default()
+        };
+        let import = import_dir(&dir.0, &options).unwrap();
+        // The broken link is reported, the loop back to the root and
+        // the second way to reach "a" are cut
+        assert_eq!(import.skipped.len(), 1);
+        assert!(import.skipped[0].0.ends_with("broken"));
+        let d = import.fs.root().subdir("d").unwrap();
+        assert!(d.subdir("link-to-a").is_none());
+        assert_eq!(d.recursive_size(), 4000 + 1000);
+        assert_eq!(import.fs.root().recursive_size(), 37257 + 1000);
+    }
+}
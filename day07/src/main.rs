@@ -1,7 +1,139 @@
+use cleanup::CleanupConstraints;
+use filesystem::{Directory, FileSystem};
+use import::{ImportOptions, Symlinks};
+use shell::Shell;
+
+mod cleanup;
+mod diff;
+mod export;
+mod filesystem;
+mod import;
+mod query;
+mod shell;
+mod transcript;
+
 fn main() {
     let input = include_str!("../input.txt");
+    for error in transcript::parse_transcript(input).1 {
+        println!("input.txt: {}", error);
+    }
     println!("Part 1: {}", part1(input));
     println!("Part 2: {}", part2(input));
+
+    // Several directories can free less space than the single one of part 2
+    let fs = parse_filesystem_from_input(input);
+    let required = required_space(fs.root(), 70000000, 30000000).unwrap();
+    let constraints = CleanupConstraints {
+        max_deletions: Some(3),
+        ..Default::default()
+    };
+    match cleanup::plan_cleanup(&fs, required, &constraints) {
+        Ok(plan) => print!("{}", plan),
+        Err(error) => println!("No cleanup plan: {:?}", error),
+    }
+
+    // `day07 shell` explores the puzzle filesystem interactively,
+    // `day07 replay <transcript>` runs the commands of a transcript on it,
+    // `day07 export tree|du|json [tree file]` prints it, or the tree read
+    // from the file, `day07 diff <old> <new> [--json]` compares two
+    // transcripts and `day07 import <path> ...` analyses a local
+    // directory instead
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        [] => {}
+        ["diff", rest @ ..] => diff_transcripts(rest),
+        ["export", rest @ ..] => export(input, rest),
+        ["import", rest @ ..] => match ImportReport::from_args(rest) {
+            Some(report) => report.run(),
+            None => println!("{}", ImportReport::USAGE),
+        },
+        ["shell"] => {
+            let mut shell = puzzle_shell(input);
+            shell
+                .repl(std::io::stdin().lock(), std::io::stdout())
+                .unwrap();
+            print_used_space(&shell);
+        }
+        ["replay", path] => {
+            let transcript = match std::fs::read_to_string(path) {
+                Ok(transcript) => transcript,
+                Err(error) => return println!("{}: {}", path, error),
+            };
+            let mut shell = puzzle_shell(input);
+            let (output, errors) = shell.replay(&transcript);
+            print!("{}", output);
+            for error in errors {
+                println!("line {}: {}", error.line, error.error);
+            }
+            print_used_space(&shell);
+        }
+        _ => println!(
+            "usage: day07 [shell | replay <transcript> | export <format> [file] | diff <old> <new> | import <path> ...]"
+        ),
+    }
+}
+
+// Shell on the puzzle filesystem, starting at the root
+fn puzzle_shell(input: &str) -> Shell {
+    let mut fs = parse_filesystem_from_input(input);
+    fs.cd_root();
+    Shell::new(fs)
+}
+
+fn print_used_space(shell: &Shell) {
+    let used_space = shell.filesystem().root().recursive_size();
+    println!("Used space: {}", used_space);
+}
+
+fn export(input: &str, args: &[&str]) {
+    let fs = match args.get(1) {
+        Some(path) => {
+            let tree = match std::fs::read_to_string(path) {
+                Ok(tree) => tree,
+                Err(error) => return println!("{}: {}", path, error),
+            };
+            match export::parse_tree(&tree) {
+                Ok(fs) => fs,
+                Err(error) => return println!("{}: {}", path, error),
+            }
+        }
+        None => parse_filesystem_from_input(input),
+    };
+    match args.first().copied() {
+        Some("tree") if args.len() <= 2 => print!("{}", export::to_tree(fs.root())),
+        Some("du") if args.len() <= 2 => print!("{}", export::to_du(fs.root())),
+        Some("json") if args.len() <= 2 => println!("{}", export::to_json(fs.root())),
+        _ => println!("usage: day07 export tree|du|json [tree file]"),
+    }
+}
+
+fn diff_transcripts(args: &[&str]) {
+    let (paths, json) = match args {
+        [old, new] => ([old, new], false),
+        [old, new, "--json"] => ([old, new], true),
+        _ => return println!("usage: day07 diff <old transcript> <new transcript> [--json]"),
+    };
+    let mut snapshots = Vec::new();
+    for path in paths {
+        let transcript = match std::fs::read_to_string(path) {
+            Ok(transcript) => transcript,
+            Err(error) => return println!("{}: {}", path, error),
+        };
+        let (fs, errors) = transcript::parse_transcript(&transcript);
+        for error in errors {
+            println!("{}: {}", path, error);
+        }
+        snapshots.push(fs);
+    }
+    let diff = diff::diff(snapshots[0].root(), snapshots[1].root());
+    if json {
+        println!("{}", diff.to_json());
+    } else if diff.is_empty() {
+        println!("No changes");
+    } else {
+        print!("{}", diff);
+    }
 }
 
 // 1. Parse the cmds and create a virtual filesystem
@@ -9,16 +141,15 @@ fn main() {
 // 3. Find all directory with size at most 100000
 // 4. Sum them
 fn part1(input: &str) -> usize {
-    let root = parse_filesystem_from_input(input);
-    get_sum_of_directory_with_max_size(&root, 100000)
+    let fs = parse_filesystem_from_input(input);
+    get_sum_of_directory_with_max_size(fs.root(), 100000)
 }
 
-fn get_sum_of_directory_with_max_size(root: &Directory, max_size: usize) -> usize {
+fn get_sum_of_directory_with_max_size(root: Directory, max_size: usize) -> usize {
     let mut sum = 0;
 
     sum += root
-        .subdirs
-        .iter()
+        .subdirs()
         .map(|d| get_sum_of_directory_with_max_size(d, max_size))
         .sum::<usize>();
 
@@ -34,257 +165,208 @@ fn get_sum_of_directory_with_max_size(root: &Directory, max_size: usize) -> usiz
 // 2. Get dir that might be deleted
 // 3. Order them by size, and take the smallest
 fn part2(input: &str) -> usize {
-    let root = parse_filesystem_from_input(input);
-    let mut directories_to_remove = find_directory_that_might_be_removed(&root, 70000000, 30000000);
+    let fs = parse_filesystem_from_input(input);
+    let mut directories_to_remove: Vec<usize> =
+        find_directory_that_might_be_removed(fs.root(), 70000000, 30000000)
+            .unwrap()
+            .iter()
+            .map(|d| d.recursive_size())
+            .collect();
     directories_to_remove.sort();
     *directories_to_remove.first().unwrap()
 }
 
-#[derive(Debug, PartialEq)]
-struct File {
-    name: String,
-    size: usize,
+// The problems of the transcript are reported once by `main`
+fn parse_filesystem_from_input(input: &str) -> FileSystem {
+    transcript::parse_transcript(input).0
 }
 
 #[derive(Debug, PartialEq)]
-struct Directory {
-    name: String,
-    subdirs: Vec<Directory>,
-    files: Vec<File>,
+enum SpaceError {
+    // Not enough space to store the file, ever forever
+    DiskTooSmall,
+    // Not enough space to store the file, right now
+    DiskOverfull,
 }
 
-impl Directory {
-    // Returns size of all files in this directory
-    // And the size of all subdirectories
-    fn recursive_size(&self) -> usize {
-        self.size()
-            + self
-                .subdirs
-                .iter()
-                .map(|d| d.recursive_size())
-                .sum::<usize>()
+// Space to free so that `total_required_space` is unused, 0 when
+// there is already enough unused space
+fn required_space(
+    root: Directory,
+    total_space: usize,
+    total_required_space: usize,
+) -> Result<usize, SpaceError> {
+    if total_space < total_required_space {
+        return Err(SpaceError::DiskTooSmall);
     }
 
-    // Returns size of all file at current directory level
-    // (not recursive)
-    fn size(&self) -> usize {
-        self.files.iter().map(|f| f.size).sum()
+    let used_space = root.recursive_size();
+    if used_space > total_space {
+        return Err(SpaceError::DiskOverfull);
     }
+    let unused_space = total_space - used_space;
+    Ok(total_required_space.saturating_sub(unused_space))
+}
 
-    fn get_dir(&self, path: &[String]) -> Option<&Directory> {
-        if path.is_empty() {
-            return Some(self);
-        }
-        if let Some(subdir) = self.get_subdir(&path[0]) {
-            return subdir.get_dir(&path[1..]);
-        }
-        None
+// Directories that free enough space once removed, none when there is
+// already enough unused space
+fn find_directory_that_might_be_removed(
+    root: Directory,
+    total_space: usize,
+    total_required_space: usize,
+) -> Result<Vec<Directory>, SpaceError> {
+    let required_space = required_space(root, total_space, total_required_space)?;
+    if required_space == 0 {
+        return Ok(Vec::new());
     }
 
-    fn get_dir_mut(&mut self, path: &[String]) -> Option<&mut Directory> {
-        if path.is_empty() {
-            return Some(self);
-        }
-        if let Some(subdir) = self.get_subdir_mut(&path[0]) {
-            return subdir.get_dir_mut(&path[1..]);
-        }
-        None
-    }
+    Ok(get_subdirs_that_have_size_greater_than(
+        root,
+        required_space,
+    ))
+}
 
-    fn get_subdir(&self, name: &str) -> Option<&Directory> {
-        self.subdirs.iter().find(|d| d.name == name)
+fn get_subdirs_that_have_size_greater_than(root: Directory, size: usize) -> Vec<Directory> {
+    let mut results = Vec::new();
+    if root.recursive_size() > size {
+        results.push(root);
     }
 
-    fn get_subdir_mut(&mut self, name: &str) -> Option<&mut Directory> {
-        self.subdirs.iter_mut().find(|d| d.name == name)
+    for subdir in root.subdirs() {
+        results.extend(get_subdirs_that_have_size_greater_than(subdir, size));
     }
 
-    fn add_subdir(&mut self, name: &str) {
-        if self.subdirs.iter().any(|d| d.name == name) {
-            return;
-        }
-        self.subdirs.push(Directory {
-            name: name.to_string(),
-            subdirs: Vec::new(),
-            files: Vec::new(),
-        });
-    }
+    results
+}
 
-    fn add_file(&mut self, name: &str, size: usize) {
-        if self.files.iter().any(|f| f.name == name) {
-            return;
-        }
-        self.files.push(File {
-            name: name.to_string(),
-            size,
-        });
-    }
+// Run the puzzle analyses on a local directory
+struct ImportReport {
+    path: String,
+    options: ImportOptions,
+    max_size: usize,
+    total_space: usize,
+    required_space: usize,
+    cleanup: CleanupConstraints,
 }
 
-fn parse_filesystem_from_input(input: &str) -> Directory {
-    #[derive(Debug, PartialEq)]
-    enum ParseState {
-        WaitingForCmd,
-        ParsingLs,
+impl ImportReport {
+    const USAGE: &'static str = "usage: day07 import <path> [--follow-symlinks] \
+[--max-depth <n>] [--max-size <bytes>] [--disk <bytes>] [--required <bytes>] \
+[--protect <path>]... [--max-deletions <n>]";
+
+    fn from_args(args: &[&str]) -> Option<Self> {
+        let (path, mut options) = args.split_first()?;
+        let mut report = ImportReport {
+            path: path.to_string(),
+            options: ImportOptions::default(),
+            max_size: 100000,
+            total_space: 70000000,
+            required_space: 30000000,
+            cleanup: CleanupConstraints::default(),
+        };
+        while let Some((option, rest)) = options.split_first() {
+            if *option == "--follow-symlinks" {
+                report.options.symlinks = Symlinks::Follow;
+                options = rest;
+                continue;
+            }
+            let (value, rest) = rest.split_first()?;
+            if *option == "--protect" {
+                report.cleanup.protected.push(value.to_string());
+                options = rest;
+                continue;
+            }
+            let value = value.parse().ok()?;
+            match *option {
+                "--max-depth" => report.options.max_depth = Some(value),
+                "--max-size" => report.max_size = value,
+                "--disk" => report.total_space = value,
+                "--required" => report.required_space = value,
+                "--max-deletions" => report.cleanup.max_deletions = Some(value),
+                _ => return None,
+            }
+            options = rest;
+        }
+        Some(report)
     }
 
-    let mut current_path = Vec::<String>::new();
-    let mut root = Directory {
-        name: String::from("/"),
-        subdirs: Vec::new(),
-        files: Vec::new(),
-    };
-    let mut state = ParseState::WaitingForCmd;
-
-    for line in input.lines() {
-        if state == ParseState::ParsingLs {
-            if line.starts_with("$") {
-                state = ParseState::WaitingForCmd;
-            } else if line.starts_with("dir") {
-                let name = line[4..].trim();
-                let current_dir = root.get_dir_mut(&current_path).unwrap();
-                current_dir.add_subdir(name);
-            } else {
-                let mut tokens = line.split(" ");
-                let size = tokens.next().unwrap().parse::<usize>().unwrap();
-                let name = tokens.next().unwrap().trim();
-                let current_dir = root.get_dir_mut(&current_path).unwrap();
-                current_dir.add_file(name, size);
+    fn run(&self) {
+        let import = match import::import_dir(self.path.as_ref(), &self.options) {
+            Ok(import) => import,
+            Err(error) => {
+                println!("{}: {}", self.path, error);
+                return;
             }
+        };
+        for (path, error) in &import.skipped {
+            println!("skipped {}: {}", path.display(), error);
         }
 
-        if state == ParseState::WaitingForCmd {
-            if line.starts_with("$ cd ") {
-                let path = line[5..].trim();
-                if path == "/" {
-                    current_path.clear();
-                } else if path == ".." {
-                    current_path.pop();
-                } else {
-                    current_path.push(path.to_string());
+        let root = import.fs.root();
+        println!("{}: {} bytes", self.path, root.recursive_size());
+        println!(
+            "Directories of at most {} bytes: {} bytes",
+            self.max_size,
+            get_sum_of_directory_with_max_size(root, self.max_size)
+        );
+        match find_directory_that_might_be_removed(root, self.total_space, self.required_space) {
+            Ok(mut candidates) => {
+                candidates.sort_by_key(|d| d.recursive_size());
+                println!("Removal candidates: {}", candidates.len());
+                for dir in candidates {
+                    println!("  {}\t{}", dir.recursive_size(), dir.path());
                 }
-            } else if line.starts_with("$ ls") {
-                state = ParseState::ParsingLs;
             }
+            Err(error) => println!("No removal candidates: {:?}", error),
         }
-    }
-
-    root
-}
-
-fn find_directory_that_might_be_removed(
-    root: &Directory,
-    total_space: usize,
-    total_required_space: usize,
-) -> Vec<usize> {
-    if total_space < total_required_space {
-        panic!("Not enough space to store the file, ever forever")
-    }
 
-    let used_space = root.recursive_size();
-    if used_space > total_space {
-        panic!("Not enough space to store the file, right now")
+        let required = required_space(root, self.total_space, self.required_space);
+        match required.map(|required| cleanup::plan_cleanup(&import.fs, required, &self.cleanup)) {
+            Ok(Ok(plan)) => print!("{}", plan),
+            Ok(Err(error)) => println!("No cleanup plan: {:?}", error),
+            Err(error) => println!("No cleanup plan: {:?}", error),
+        }
     }
-    let unused_space = total_space - used_space;
-    let required_space = total_required_space - unused_space;
-
-    get_subdirs_that_have_size_greater_than(root, required_space)
-}
-
-fn get_subdirs_that_have_size_greater_than(root: &Directory, size: usize) -> Vec<usize> {
-    let mut results = Vec::new();
-    if root.recursive_size() > size {
-        results.push(root.recursive_size());
-    }
-
-    for subdir in &root.subdirs {
-        results.extend(get_subdirs_that_have_size_greater_than(subdir, size));
-    }
-
-    results
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::filesystem::tests::{get_dir, size};
 
     #[test]
-    fn test_get_subdirs_mut() {
-        let mut root = Directory {
-            name: String::from("/"),
-            subdirs: vec![Directory {
-                name: String::from("a"),
-                subdirs: Vec::new(),
-                files: Vec::new(),
-            }],
-            files: Vec::new(),
-        };
+    fn test_get_subdir() {
+        let mut fs = FileSystem::new();
+        fs.add_subdir(FileSystem::ROOT, "a");
 
-        assert_eq!(root.get_subdir_mut("a").unwrap().name, "a");
-        assert_eq!(root.get_subdir_mut("b"), None);
+        assert_eq!(fs.root().subdir("a").unwrap().name(), "a");
+        assert!(fs.root().subdir("b").is_none());
     }
 
     #[test]
-    fn test_get_dir_mut() {
-        let mut root = Directory {
-            name: String::from("/"),
-            subdirs: vec![Directory {
-                name: String::from("a"),
-                subdirs: vec![Directory {
-                    name: String::from("b"),
-                    subdirs: Vec::new(),
-                    files: Vec::new(),
-                }],
-                files: Vec::new(),
-            }],
-            files: Vec::new(),
-        };
-
-        assert_eq!(
-            root.get_dir_mut(&vec![String::from("a")]).unwrap().name,
-            "a"
-        );
-        assert_eq!(root.get_dir_mut(&vec![String::from("b")]), None);
-        assert_eq!(
-            root.get_dir_mut(&vec![String::from("a"), String::from("b")])
-                .unwrap()
-                .name,
-            "b"
-        );
+    fn test_get_dir() {
+        let mut fs = FileSystem::new();
+        let a = fs.add_subdir(FileSystem::ROOT, "a");
+        fs.add_subdir(a, "b");
+
+        assert_eq!(get_dir(fs.root(), &["a"]).unwrap().name(), "a");
+        assert!(get_dir(fs.root(), &["b"]).is_none());
+        assert_eq!(get_dir(fs.root(), &["a", "b"]).unwrap().name(), "b");
     }
 
     #[test]
     fn test_get_size() {
-        let root = Directory {
-            name: String::from("/"),
-            subdirs: vec![Directory {
-                name: String::from("a"),
-                subdirs: vec![Directory {
-                    name: String::from("b"),
-                    subdirs: Vec::new(),
-                    files: vec![File {
-                        name: String::from("c"),
-                        size: 10,
-                    }],
-                }],
-                files: vec![
-                    File {
-                        name: String::from("d"),
-                        size: 20,
-                    },
-                    File {
-                        name: String::from("e"),
-                        size: 30,
-                    },
-                ],
-            }],
-            files: Vec::new(),
-        };
+        let mut fs = FileSystem::new();
+        let a = fs.add_subdir(FileSystem::ROOT, "a");
+        let b = fs.add_subdir(a, "b");
+        fs.add_file(b, "c", 10);
+        fs.add_file(a, "d", 20);
+        fs.add_file(a, "e", 30);
 
-        assert_eq!(root.size(), 0);
-        assert_eq!(root.get_subdir("a").unwrap().size(), 50);
+        assert_eq!(size(fs.root()), 0);
+        assert_eq!(size(fs.dir(a)), 50);
 
-        assert_eq!(root.recursive_size(), 60);
+        assert_eq!(fs.root().recursive_size(), 60);
     }
 
     static _EXAMPLE_CMDS: &str = r#"
@@ -328,118 +410,40 @@ $ ls
     //     - d.log (file, size=8033020)
     //     - d.ext (file, size=5626152)
     //     - k (file, size=7214296)
-    fn get_example_filesystem() -> Directory {
-        Directory {
-            name: "/".to_string(),
-            subdirs: vec![
-                Directory {
-                    name: "a".to_string(),
-                    subdirs: vec![Directory {
-                        name: "e".to_string(),
-                        subdirs: Vec::new(),
-                        files: vec![File {
-                            name: "i".to_string(),
-                            size: 584,
-                        }],
-                    }],
-                    files: vec![
-                        File {
-                            name: "f".to_string(),
-                            size: 29116,
-                        },
-                        File {
-                            name: "g".to_string(),
-                            size: 2557,
-                        },
-                        File {
-                            name: "h.lst".to_string(),
-                            size: 62596,
-                        },
-                    ],
-                },
-                Directory {
-                    name: "d".to_string(),
-                    subdirs: Vec::new(),
-                    files: vec![
-                        File {
-                            name: "j".to_string(),
-                            size: 4060174,
-                        },
-                        File {
-                            name: "d.log".to_string(),
-                            size: 8033020,
-                        },
-                        File {
-                            name: "d.ext".to_string(),
-                            size: 5626152,
-                        },
-                        File {
-                            name: "k".to_string(),
-                            size: 7214296,
-                        },
-                    ],
-                },
-            ],
-            files: vec![
-                File {
-                    name: "b.txt".to_string(),
-                    size: 14848514,
-                },
-                File {
-                    name: "c.dat".to_string(),
-                    size: 8504156,
-                },
-            ],
-        }
+    fn get_example_filesystem() -> FileSystem {
+        export::parse_tree(
+            "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - j (file, size=4060174)
+    - d.log (file, size=8033020)
+    - d.ext (file, size=5626152)
+    - k (file, size=7214296)
+",
+        )
+        .unwrap()
     }
 
     #[test]
     fn test_total_size_e() {
+        let fs = get_example_filesystem();
         assert_eq!(
-            Directory {
-                name: "e".to_string(),
-                subdirs: Vec::new(),
-                files: vec![File {
-                    name: "i".to_string(),
-                    size: 584,
-                }],
-            }
-            .recursive_size(),
+            get_dir(fs.root(), &["a", "e"]).unwrap().recursive_size(),
             584
         );
     }
 
     #[test]
     fn test_get_total_size_a() {
-        assert_eq!(
-            Directory {
-                name: "a".to_string(),
-                subdirs: vec![Directory {
-                    name: "e".to_string(),
-                    subdirs: Vec::new(),
-                    files: vec![File {
-                        name: "i".to_string(),
-                        size: 584,
-                    }],
-                }],
-                files: vec![
-                    File {
-                        name: "f".to_string(),
-                        size: 29116,
-                    },
-                    File {
-                        name: "g".to_string(),
-                        size: 2557,
-                    },
-                    File {
-                        name: "h.lst".to_string(),
-                        size: 62596,
-                    },
-                ],
-            }
-            .recursive_size(),
-            94853
-        );
+        let fs = get_example_filesystem();
+        assert_eq!(get_dir(fs.root(), &["a"]).unwrap().recursive_size(), 94853);
     }
 
     #[test]
@@ -455,6 +459,29 @@ $ ls
         assert_eq!(part1(_EXAMPLE_CMDS), 95437);
     }
 
+    #[test]
+    fn test_find_directory_that_might_be_removed() {
+        let fs = get_example_filesystem();
+        let paths =
+            |dirs: Vec<Directory>| -> Vec<String> { dirs.iter().map(|d| d.path()).collect() };
+        assert_eq!(
+            find_directory_that_might_be_removed(fs.root(), 70000000, 30000000).map(paths),
+            Ok(vec!["/".to_string(), "/d".to_string()])
+        );
+        assert_eq!(
+            find_directory_that_might_be_removed(fs.root(), 80000000, 30000000).map(paths),
+            Ok(vec![])
+        );
+        assert_eq!(
+            find_directory_that_might_be_removed(fs.root(), 1000, 2000).map(paths),
+            Err(SpaceError::DiskTooSmall)
+        );
+        assert_eq!(
+            find_directory_that_might_be_removed(fs.root(), 40000000, 2000).map(paths),
+            Err(SpaceError::DiskOverfull)
+        );
+    }
+
     #[test]
     fn test_part2() {
         assert_eq!(part2(_EXAMPLE_CMDS), 24933642);
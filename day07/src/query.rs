@@ -0,0 +1,417 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::filesystem::{DirId, Directory, FileSystem};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Dir,
+    File,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Kind::Dir => write!(f, "dir"),
+            Kind::File => write!(f, "file"),
+        }
+    }
+}
+
+// An entry found by a query, the size of a directory is its recursive size
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryMatch {
+    pub path: String,
+    pub size: usize,
+    pub kind: Kind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum QueryError {
+    // ".." after a wildcard, it could lead back to several directories
+    InvalidPattern { pattern: String },
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryError::InvalidPattern { pattern } => {
+                write!(f, "{}: \"..\" can't follow a wildcard", pattern)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Component {
+    Literal(String),
+    // A name with `*` for any characters and `?` for one character
+    Wildcard(String),
+    // `**`, any number of directories
+    AnyDepth,
+}
+
+impl Component {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Component::Literal(literal) => literal == name,
+            Component::Wildcard(pattern) => {
+                let pattern: Vec<char> = pattern.chars().collect();
+                let name: Vec<char> = name.chars().collect();
+                wildcard_matches(&pattern, &name)
+            }
+            Component::AnyDepth => true,
+        }
+    }
+}
+
+// Match with backtracking to the last `*` only, which is enough as a
+// `*` can always absorb what an earlier one would have
+fn wildcard_matches(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// A path pattern made absolute, with "." and ".." already applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glob {
+    components: Vec<Component>,
+}
+
+impl Glob {
+    // Relative patterns start at `from`, the parent of the root is itself
+    pub fn new(fs: &FileSystem, from: DirId, pattern: &str) -> Result<Self, QueryError> {
+        let mut components: Vec<Component> = Vec::new();
+        if !pattern.starts_with('/') {
+            let mut dir = Some(fs.dir(from));
+            while let Some(ancestor) = dir.filter(|d| d.parent().is_some()) {
+                components.push(Component::Literal(ancestor.name().to_string()));
+                dir = ancestor.parent();
+            }
+            components.reverse();
+        }
+
+        for component in pattern.split('/').filter(|c| !c.is_empty()) {
+            match component {
+                "." => {}
+                ".." => match components.pop() {
+                    Some(Component::Literal(_)) | None => {}
+                    Some(_) => {
+                        return Err(QueryError::InvalidPattern {
+                            pattern: pattern.to_string(),
+                        })
+                    }
+                },
+                "**" => {
+                    // Consecutive `**` match the same paths as a single one
+                    if components.last() != Some(&Component::AnyDepth) {
+                        components.push(Component::AnyDepth);
+                    }
+                }
+                name if name.contains(['*', '?']) => {
+                    components.push(Component::Wildcard(name.to_string()))
+                }
+                name => components.push(Component::Literal(name.to_string())),
+            }
+        }
+        Ok(Glob { components })
+    }
+
+    // Add the positions reachable without consuming a name
+    fn closure(&self, positions: &mut Vec<usize>) {
+        let mut idx = 0;
+        while idx < positions.len() {
+            let position = positions[idx];
+            if self.components.get(position) == Some(&Component::AnyDepth)
+                && !positions.contains(&(position + 1))
+            {
+                positions.push(position + 1);
+            }
+            idx += 1;
+        }
+    }
+
+    // Positions after matching `name` from `positions`
+    fn step(&self, positions: &[usize], name: &str) -> Vec<usize> {
+        let mut next = Vec::new();
+        for position in positions {
+            let Some(component) = self.components.get(*position) else {
+                continue;
+            };
+            let position = match component {
+                Component::AnyDepth => *position,
+                _ => *position + 1,
+            };
+            if component.matches(name) && !next.contains(&position) {
+                next.push(position);
+            }
+        }
+        self.closure(&mut next);
+        next
+    }
+
+    fn is_complete(&self, positions: &[usize]) -> bool {
+        positions.contains(&self.components.len())
+    }
+}
+
+// Filters applied to the entries matching the pattern, sizes are
+// inclusive bounds
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    pub min_size: Option<usize>,
+    pub max_size: Option<usize>,
+    // Only files whose name ends with "." and this extension
+    pub extension: Option<String>,
+    pub kind: Option<Kind>,
+}
+
+impl Query {
+    fn accepts(&self, name: &str, size: usize, kind: Kind) -> bool {
+        self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+            && self.kind.is_none_or(|expected| kind == expected)
+            && self.extension.as_ref().is_none_or(|extension| {
+                kind == Kind::File
+                    && name
+                        .rsplit_once('.')
+                        .is_some_and(|(stem, ext)| !stem.is_empty() && ext == extension)
+            })
+    }
+}
+
+// Lazy walk of the directories that can still match the pattern, each
+// entry is visited at most once
+pub struct Matches<'a> {
+    glob: Glob,
+    query: Query,
+    // Directories to visit with their positions in the pattern
+    stack: Vec<(Directory<'a>, Vec<usize>)>,
+    pending: VecDeque<QueryMatch>,
+}
+
+impl Iterator for Matches<'_> {
+    type Item = QueryMatch;
+
+    fn next(&mut self) -> Option<QueryMatch> {
+        loop {
+            if let Some(found) = self.pending.pop_front() {
+                return Some(found);
+            }
+            let (dir, positions) = self.stack.pop()?;
+
+            if self.glob.is_complete(&positions)
+                && self
+                    .query
+                    .accepts(dir.name(), dir.recursive_size(), Kind::Dir)
+            {
+                self.pending.push_back(QueryMatch {
+                    path: dir.path(),
+                    size: dir.recursive_size(),
+                    kind: Kind::Dir,
+                });
+            }
+            for file in dir.files() {
+                let next = self.glob.step(&positions, &file.name);
+                if self.glob.is_complete(&next)
+                    && self.query.accepts(&file.name, file.size, Kind::File)
+                {
+                    self.pending.push_back(QueryMatch {
+                        path: dir.child_path(&file.name),
+                        size: file.size,
+                        kind: Kind::File,
+                    });
+                }
+            }
+            let subdirs: Vec<Directory> = dir.subdirs().collect();
+            for subdir in subdirs.into_iter().rev() {
+                let next = self.glob.step(&positions, subdir.name());
+                if !next.is_empty() {
+                    self.stack.push((subdir, next));
+                }
+            }
+        }
+    }
+}
+
+// Entries matching the glob pattern and the filters, each directory
+// before its files and then its subdirectories
+pub fn query<'a>(
+    fs: &'a FileSystem,
+    from: DirId,
+    pattern: &str,
+    query: &Query,
+) -> Result<Matches<'a>, QueryError> {
+    let glob = Glob::new(fs, from, pattern)?;
+    let mut positions = vec![0];
+    glob.closure(&mut positions);
+    Ok(Matches {
+        glob,
+        query: query.clone(),
+        stack: vec![(fs.root(), positions)],
+        pending: VecDeque::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export;
+
+    fn example() -> FileSystem {
+        export::parse_tree(
+            "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+      - x.lst (file, size=10)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+    - small.log (file, size=12)
+",
+        )
+        .unwrap()
+    }
+
+    fn paths(fs: &FileSystem, from: DirId, pattern: &str, query: &Query) -> Vec<String> {
+        super::query(fs, from, pattern, query)
+            .unwrap()
+            .map(|found| found.path)
+            .collect()
+    }
+
+    #[test]
+    fn test_wildcards() {
+        let matches = |pattern: &str, name: &str| {
+            wildcard_matches(
+                &pattern.chars().collect::<Vec<_>>(),
+                &name.chars().collect::<Vec<_>>(),
+            )
+        };
+        assert!(matches("*.lst", "h.lst"));
+        assert!(matches("*", ""));
+        assert!(matches("d.*", "d.log"));
+        assert!(matches("?.*", "d.log"));
+        assert!(matches("*a*b*", "xxaxxbxxb"));
+        assert!(!matches("*.lst", "h.lst.bak"));
+        assert!(!matches("?", "ab"));
+        assert!(!matches("a*b", "acbd"));
+    }
+
+    #[test]
+    fn test_glob() {
+        let fs = example();
+        let root = FileSystem::ROOT;
+        let none = Query::default();
+        assert_eq!(
+            paths(&fs, root, "/a/**/*.lst", &none),
+            ["/a/h.lst", "/a/e/x.lst"].map(String::from)
+        );
+        assert_eq!(
+            paths(&fs, root, "**/*.lst", &none),
+            ["/a/h.lst", "/a/e/x.lst"].map(String::from)
+        );
+        assert_eq!(
+            paths(&fs, root, "/*", &none),
+            ["/b.txt", "/c.dat", "/a", "/d"].map(String::from)
+        );
+        assert_eq!(paths(&fs, root, "/", &none), ["/"].map(String::from));
+        // The root, 3 directories and 12 files
+        assert_eq!(paths(&fs, root, "/**", &none).len(), 16);
+        assert_eq!(
+            paths(&fs, root, "/a/?", &none),
+            ["/a/f", "/a/g", "/a/e"].map(String::from)
+        );
+        assert!(paths(&fs, root, "/missing/**", &none).is_empty());
+
+        // Relative patterns, "." and ".."
+        let e = fs.root().subdir("a").unwrap().subdir("e").unwrap().id();
+        assert_eq!(paths(&fs, e, "i", &none), ["/a/e/i"].map(String::from));
+        assert_eq!(
+            paths(&fs, e, "./../*.lst", &none),
+            ["/a/h.lst"].map(String::from)
+        );
+        assert_eq!(
+            paths(&fs, e, "../../../d/j", &none),
+            ["/d/j"].map(String::from)
+        );
+        assert_eq!(paths(&fs, e, "..", &none), ["/a"].map(String::from));
+        assert_eq!(
+            query(&fs, e, "/a/*/..", &none).err(),
+            Some(QueryError::InvalidPattern {
+                pattern: "/a/*/..".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        let fs = example();
+        let root = FileSystem::ROOT;
+
+        // All .log files over 5MB
+        let logs = Query {
+            min_size: Some(5 << 20),
+            extension: Some("log".to_string()),
+            ..Default::default()
+        };
+        let found: Vec<QueryMatch> = query(&fs, root, "/**", &logs).unwrap().collect();
+        assert_eq!(
+            found,
+            vec![QueryMatch {
+                path: "/d/d.log".to_string(),
+                size: 8033020,
+                kind: Kind::File,
+            }]
+        );
+
+        let small_dirs = Query {
+            max_size: Some(100000),
+            kind: Some(Kind::Dir),
+            ..Default::default()
+        };
+        assert_eq!(
+            paths(&fs, root, "/**", &small_dirs),
+            ["/a", "/a/e"].map(String::from)
+        );
+
+        let range = Query {
+            min_size: Some(584),
+            max_size: Some(2557),
+            ..Default::default()
+        };
+        assert_eq!(
+            paths(&fs, root, "**", &range),
+            ["/a/g", "/a/e", "/a/e/i"].map(String::from)
+        );
+    }
+}
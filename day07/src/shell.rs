@@ -0,0 +1,833 @@
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use crate::export;
+use crate::filesystem::{DirId, Directory, Entry, FileSystem};
+use crate::query::{self, Kind, Query};
+
+#[derive(Debug, PartialEq)]
+pub enum ShellError {
+    UnknownCommand { command: String },
+    // Missing or unexpected arguments
+    Usage { usage: &'static str },
+    NotFound { path: String },
+    NotADirectory { path: String },
+    // `rm` without `-r` on a directory
+    IsADirectory { path: String },
+    AlreadyExists { path: String },
+    InvalidName { path: String },
+    InvalidSize { size: String },
+    // Removing the root, or moving a directory inside itself
+    InvalidTarget { path: String },
+    InvalidPattern { pattern: String },
+}
+
+impl fmt::Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShellError::UnknownCommand { command } => write!(f, "{}: command not found", command),
+            ShellError::Usage { usage } => write!(f, "usage: {}", usage),
+            ShellError::NotFound { path } => write!(f, "{}: no such file or directory", path),
+            ShellError::NotADirectory { path } => write!(f, "{}: not a directory", path),
+            ShellError::IsADirectory { path } => write!(f, "{}: is a directory", path),
+            ShellError::AlreadyExists { path } => write!(f, "{}: already exists", path),
+            ShellError::InvalidName { path } => write!(f, "{}: invalid name", path),
+            ShellError::InvalidSize { size } => write!(f, "{}: invalid size", size),
+            ShellError::InvalidTarget { path } => write!(f, "{}: invalid target", path),
+            ShellError::InvalidPattern { pattern } => write!(f, "{}: invalid pattern", pattern),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ReplayError {
+    pub line: usize,
+    pub error: ShellError,
+}
+
+// Size comparison of `find -size`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SizeFilter {
+    Exactly(usize),
+    MoreThan(usize),
+    LessThan(usize),
+}
+
+impl SizeFilter {
+    // "+N", "-N" or "N", with an optional k, M or G suffix
+    fn parse(arg: &str) -> Result<SizeFilter, ShellError> {
+        let invalid = || ShellError::InvalidSize {
+            size: arg.to_string(),
+        };
+        let (filter, rest): (fn(usize) -> SizeFilter, &str) = match arg.as_bytes().first() {
+            Some(b'+') => (SizeFilter::MoreThan, &arg[1..]),
+            Some(b'-') => (SizeFilter::LessThan, &arg[1..]),
+            _ => (SizeFilter::Exactly, arg),
+        };
+        let size = parse_size(rest).map_err(|_| invalid())?;
+        Ok(filter(size))
+    }
+
+    fn matches(&self, size: usize) -> bool {
+        match self {
+            SizeFilter::Exactly(expected) => size == *expected,
+            SizeFilter::MoreThan(min) => size > *min,
+            SizeFilter::LessThan(max) => size < *max,
+        }
+    }
+}
+
+// "N" with an optional k, M or G suffix
+fn parse_size(arg: &str) -> Result<usize, ShellError> {
+    let invalid = || ShellError::InvalidSize {
+        size: arg.to_string(),
+    };
+    let (digits, unit) = match arg.char_indices().last() {
+        Some((idx, 'k')) => (&arg[..idx], 1 << 10),
+        Some((idx, 'M')) => (&arg[..idx], 1 << 20),
+        Some((idx, 'G')) => (&arg[..idx], 1 << 30),
+        _ => (arg, 1),
+    };
+    let size = digits.parse::<usize>().map_err(|_| invalid())?;
+    size.checked_mul(unit).ok_or_else(invalid)
+}
+
+// Size with a K, M, G or T suffix like `du -h`
+fn human_size(size: usize) -> String {
+    let mut value = size as f64;
+    for unit in ["", "K", "M", "G", "T"] {
+        if value < 1024.0 || unit == "T" {
+            return if unit.is_empty() {
+                size.to_string()
+            } else if value < 10.0 {
+                format!("{:.1}{}", value, unit)
+            } else {
+                format!("{:.0}{}", value, unit)
+            };
+        }
+        value /= 1024.0;
+    }
+    unreachable!()
+}
+
+// Shell commands over a virtual filesystem, each command returns its
+// output or an error and leaves the filesystem unchanged on error
+pub struct Shell {
+    fs: FileSystem,
+}
+
+impl Shell {
+    pub fn new(fs: FileSystem) -> Self {
+        Shell { fs }
+    }
+
+    pub fn filesystem(&self) -> &FileSystem {
+        &self.fs
+    }
+
+    pub fn run(&mut self, line: &str) -> Result<String, ShellError> {
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            return Ok(String::new());
+        };
+        let args: Vec<&str> = words.collect();
+        match command {
+            "cd" => self.cd(&args),
+            "ls" => self.ls(&args),
+            "pwd" => self.pwd(&args),
+            "mkdir" => self.mkdir(&args),
+            "touch" => self.touch(&args),
+            "rm" => self.rm(&args),
+            "mv" => self.mv(&args),
+            "du" => self.du(&args),
+            "find" => self.find(&args),
+            "glob" => self.glob(&args),
+            "tree" => self.tree(&args),
+            _ => Err(ShellError::UnknownCommand {
+                command: command.to_string(),
+            }),
+        }
+    }
+
+    // Run the "$ " lines of a transcript, other lines are the recorded
+    // output and are ignored. Errors are reported in the output and
+    // returned with their line number, the replay goes on.
+    pub fn replay(&mut self, transcript: &str) -> (String, Vec<ReplayError>) {
+        let mut output = String::new();
+        let mut errors = Vec::new();
+        for (idx, line) in transcript.lines().enumerate() {
+            let Some(command) = line.strip_prefix("$ ") else {
+                continue;
+            };
+            output += line;
+            output.push('\n');
+            match self.run(command) {
+                Ok(result) => output += &result,
+                Err(error) => {
+                    output += &format!("error: {}\n", error);
+                    errors.push(ReplayError {
+                        line: idx + 1,
+                        error,
+                    });
+                }
+            }
+        }
+        (output, errors)
+    }
+
+    // Read commands until "exit" or the end of the input
+    pub fn repl<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        let mut lines = input.lines();
+        loop {
+            write!(output, "{} $ ", self.fs.dir(self.fs.cwd()).path())?;
+            output.flush()?;
+            let Some(line) = lines.next() else {
+                writeln!(output)?;
+                return Ok(());
+            };
+            let line = line?;
+            if line.trim() == "exit" {
+                return Ok(());
+            }
+            match self.run(&line) {
+                Ok(result) => write!(output, "{}", result)?,
+                Err(error) => writeln!(output, "error: {}", error)?,
+            }
+        }
+    }
+
+    fn entry(&self, path: &str) -> Result<Entry<'_>, ShellError> {
+        self.fs
+            .resolve(self.fs.cwd(), path)
+            .ok_or_else(|| ShellError::NotFound {
+                path: path.to_string(),
+            })
+    }
+
+    fn directory(&self, path: &str) -> Result<Directory<'_>, ShellError> {
+        match self.entry(path)? {
+            Entry::Dir(dir) => Ok(dir),
+            Entry::File(..) => Err(ShellError::NotADirectory {
+                path: path.to_string(),
+            }),
+        }
+    }
+
+    // Directory that will contain a new entry, and the entry name
+    fn new_entry<'p>(&self, path: &'p str) -> Result<(DirId, &'p str), ShellError> {
+        let trimmed = path.trim_end_matches('/');
+        let (parent, name) = match trimmed.rsplit_once('/') {
+            Some(("", name)) => ("/", name),
+            Some((parent, name)) => (parent, name),
+            None => (".", trimmed),
+        };
+        if matches!(name, "" | "." | "..") {
+            return Err(ShellError::InvalidName {
+                path: path.to_string(),
+            });
+        }
+        let parent = self.directory(parent)?;
+        if parent.has_entry(name) {
+            return Err(ShellError::AlreadyExists {
+                path: path.to_string(),
+            });
+        }
+        Ok((parent.id(), name))
+    }
+
+    fn cd(&mut self, args: &[&str]) -> Result<String, ShellError> {
+        let dir = match args {
+            [] => FileSystem::ROOT,
+            [path] => self.directory(path)?.id(),
+            _ => return Err(ShellError::Usage { usage: "cd [dir]" }),
+        };
+        self.fs.set_cwd(dir);
+        Ok(String::new())
+    }
+
+    fn ls(&self, args: &[&str]) -> Result<String, ShellError> {
+        let entry = match args {
+            [] => self.entry(".")?,
+            [path] => self.entry(path)?,
+            _ => return Err(ShellError::Usage { usage: "ls [path]" }),
+        };
+        let mut output = String::new();
+        match entry {
+            Entry::Dir(dir) => {
+                for subdir in dir.subdirs() {
+                    output += &format!("dir {}\n", subdir.name());
+                }
+                for file in dir.files() {
+                    output += &format!("{} {}\n", file.size, file.name);
+                }
+            }
+            Entry::File(_, file) => output += &format!("{} {}\n", file.size, file.name),
+        }
+        Ok(output)
+    }
+
+    fn pwd(&self, args: &[&str]) -> Result<String, ShellError> {
+        if !args.is_empty() {
+            return Err(ShellError::Usage { usage: "pwd" });
+        }
+        Ok(format!("{}\n", self.fs.dir(self.fs.cwd()).path()))
+    }
+
+    fn mkdir(&mut self, args: &[&str]) -> Result<String, ShellError> {
+        let [path] = args else {
+            return Err(ShellError::Usage {
+                usage: "mkdir <dir>",
+            });
+        };
+        let (parent, name) = self.new_entry(path)?;
+        self.fs.add_subdir(parent, name);
+        Ok(String::new())
+    }
+
+    fn touch(&mut self, args: &[&str]) -> Result<String, ShellError> {
+        let [size, path] = args else {
+            return Err(ShellError::Usage {
+                usage: "touch <size> <file>",
+            });
+        };
+        let size = size.parse::<usize>().map_err(|_| ShellError::InvalidSize {
+            size: size.to_string(),
+        })?;
+        let (parent, name) = self.new_entry(path)?;
+        self.fs.add_file(parent, name, size);
+        Ok(String::new())
+    }
+
+    fn rm(&mut self, args: &[&str]) -> Result<String, ShellError> {
+        let (recursive, path) = match args {
+            [path] => (false, path),
+            ["-r", path] => (true, path),
+            _ => {
+                return Err(ShellError::Usage {
+                    usage: "rm [-r] <path>",
+                })
+            }
+        };
+        match self.entry(path)? {
+            Entry::File(dir, file) => {
+                let (dir, name) = (dir.id(), file.name.clone());
+                self.fs.remove_file(dir, &name);
+            }
+            Entry::Dir(_) if !recursive => {
+                return Err(ShellError::IsADirectory {
+                    path: path.to_string(),
+                })
+            }
+            Entry::Dir(dir) => {
+                let dir = dir.id();
+                if !self.fs.remove_dir(dir) {
+                    return Err(ShellError::InvalidTarget {
+                        path: path.to_string(),
+                    });
+                }
+            }
+        }
+        Ok(String::new())
+    }
+
+    // Move into `to` when it is a directory, else rename to its last
+    // component
+    fn mv(&mut self, args: &[&str]) -> Result<String, ShellError> {
+        let [from, to] = args else {
+            return Err(ShellError::Usage {
+                usage: "mv <from> <to>",
+            });
+        };
+        let (source_dir, source_file) = match self.entry(from)? {
+            Entry::Dir(dir) => (dir.id(), None),
+            Entry::File(dir, file) => (dir.id(), Some(file.name.clone())),
+        };
+        let (target, name) = match self.entry(to) {
+            Ok(Entry::Dir(dir)) => {
+                let name = match &source_file {
+                    Some(name) => name.clone(),
+                    None => self.fs.dir(source_dir).name().to_string(),
+                };
+                (dir.id(), name)
+            }
+            Ok(Entry::File(..)) => {
+                return Err(ShellError::AlreadyExists {
+                    path: to.to_string(),
+                })
+            }
+            Err(_) => {
+                let (dir, name) = self.new_entry(to)?;
+                (dir, name.to_string())
+            }
+        };
+
+        let target_dir = self.fs.dir(target);
+        if target_dir.has_entry(&name) {
+            return Err(ShellError::AlreadyExists {
+                path: target_dir.child_path(&name),
+            });
+        }
+        let moved = match &source_file {
+            Some(file) => self.fs.move_file(source_dir, file, target, &name),
+            None => self.fs.move_dir(source_dir, target, &name),
+        };
+        if !moved {
+            return Err(ShellError::InvalidTarget {
+                path: to.to_string(),
+            });
+        }
+        Ok(String::new())
+    }
+
+    // Recursive size of every directory, subdirectories first
+    fn du(&self, args: &[&str]) -> Result<String, ShellError> {
+        let (human, path) = match args {
+            [] => (false, "."),
+            ["-h"] => (true, "."),
+            [path] => (false, *path),
+            ["-h", path] => (true, *path),
+            _ => {
+                return Err(ShellError::Usage {
+                    usage: "du [-h] [dir]",
+                })
+            }
+        };
+
+        fn walk(dir: Directory, human: bool, output: &mut String) {
+            for subdir in dir.subdirs() {
+                walk(subdir, human, output);
+            }
+            let size = if human {
+                human_size(dir.recursive_size())
+            } else {
+                dir.recursive_size().to_string()
+            };
+            *output += &format!("{}\t{}\n", size, dir.path());
+        }
+
+        let mut output = String::new();
+        walk(self.directory(path)?, human, &mut output);
+        Ok(output)
+    }
+
+    // find [dir] [-type d|f] [-size [+|-]N[k|M|G]], the size of a
+    // directory is its recursive size
+    fn find(&self, args: &[&str]) -> Result<String, ShellError> {
+        let usage = ShellError::Usage {
+            usage: "find [dir] [-type d|f] [-size [+|-]N[k|M|G]]",
+        };
+        let (path, mut options) = match args.split_first() {
+            Some((path, options)) if !path.starts_with('-') => (*path, options),
+            _ => (".", args),
+        };
+        let (mut dirs, mut files, mut filter) = (true, true, None);
+        while let Some((option, rest)) = options.split_first() {
+            let (value, rest) = rest.split_first().ok_or(ShellError::Usage {
+                usage: "find [dir] [-type d|f] [-size [+|-]N[k|M|G]]",
+            })?;
+            match (*option, *value) {
+                ("-type", "d") => files = false,
+                ("-type", "f") => dirs = false,
+                ("-size", size) => filter = Some(SizeFilter::parse(size)?),
+                _ => return Err(usage),
+            }
+            options = rest;
+        }
+        let matches = |size| filter.is_none_or(|filter: SizeFilter| filter.matches(size));
+
+        fn walk(dir: Directory, visit: &mut dyn FnMut(Directory)) {
+            visit(dir);
+            for subdir in dir.subdirs() {
+                walk(subdir, visit);
+            }
+        }
+
+        let mut output = String::new();
+        walk(self.directory(path)?, &mut |dir| {
+            if dirs && matches(dir.recursive_size()) {
+                output += &format!("{}\n", dir.path());
+            }
+            for file in dir.files().iter().filter(|f| files && matches(f.size)) {
+                let separator = if dir.parent().is_some() { "/" } else { "" };
+                output += &format!("{}{}{}\n", dir.path(), separator, file.name);
+            }
+        });
+        Ok(output)
+    }
+
+    // glob <pattern> [-type d|f] [-min N[k|M|G]] [-max N[k|M|G]] [-ext <extension>],
+    // the size of a directory is its recursive size
+    fn glob(&self, args: &[&str]) -> Result<String, ShellError> {
+        let usage = || ShellError::Usage {
+            usage: "glob <pattern> [-type d|f] [-min N[k|M|G]] [-max N[k|M|G]] [-ext <extension>]",
+        };
+        let (pattern, mut options) = args.split_first().ok_or_else(usage)?;
+        let mut filters = Query::default();
+        while let Some((option, rest)) = options.split_first() {
+            let (value, rest) = rest.split_first().ok_or_else(usage)?;
+            match (*option, *value) {
+                ("-type", "d") => filters.kind = Some(Kind::Dir),
+                ("-type", "f") => filters.kind = Some(Kind::File),
+                ("-min", size) => filters.min_size = Some(parse_size(size)?),
+                ("-max", size) => filters.max_size = Some(parse_size(size)?),
+                ("-ext", extension) => filters.extension = Some(extension.to_string()),
+                _ => return Err(usage()),
+            }
+            options = rest;
+        }
+
+        let matches = query::query(&self.fs, self.fs.cwd(), pattern, &filters).map_err(
+            |query::QueryError::InvalidPattern { pattern }| ShellError::InvalidPattern { pattern },
+        )?;
+        Ok(matches
+            .map(|found| format!("{}\t{}\t{}\n", found.kind, found.size, found.path))
+            .collect())
+    }
+
+    // Draw the tree like the puzzle statement, entries sorted by name
+    fn tree(&self, args: &[&str]) -> Result<String, ShellError> {
+        let dir = match args {
+            [] => self.directory(".")?,
+            [path] => self.directory(path)?,
+            _ => {
+                return Err(ShellError::Usage {
+                    usage: "tree [dir]",
+                })
+            }
+        };
+
+        Ok(export::to_tree(dir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_shell() -> Shell {
+        let mut shell = Shell::new(FileSystem::new());
+        let (_, errors) = shell.replay(
+            "$ mkdir a
+$ mkdir /a/e
+$ touch 584 a/e/i
+$ touch 29116 a/f
+$ touch 2557 a/g
+$ touch 62596 a/h.lst
+$ touch 14848514 b.txt
+$ touch 8504156 c.dat
+$ mkdir d
+$ cd d
+$ touch 4060174 j
+$ touch 8033020 d.log
+$ touch 5626152 d.ext
+$ touch 7214296 k
+$ cd
+",
+        );
+        assert_eq!(errors, vec![]);
+        shell
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut shell = example_shell();
+        assert_eq!(shell.run("pwd"), Ok("/\n".to_string()));
+        assert_eq!(shell.run("cd a/e"), Ok(String::new()));
+        assert_eq!(shell.run("pwd"), Ok("/a/e\n".to_string()));
+        assert_eq!(shell.run("cd ../../d"), Ok(String::new()));
+        assert_eq!(shell.run("pwd"), Ok("/d\n".to_string()));
+        assert_eq!(shell.run("cd /"), Ok(String::new()));
+        assert_eq!(shell.run("cd .."), Ok(String::new()));
+        assert_eq!(shell.run("pwd"), Ok("/\n".to_string()));
+
+        assert_eq!(
+            shell.run("cd x"),
+            Err(ShellError::NotFound {
+                path: "x".to_string()
+            })
+        );
+        assert_eq!(
+            shell.run("cd b.txt"),
+            Err(ShellError::NotADirectory {
+                path: "b.txt".to_string()
+            })
+        );
+        assert_eq!(
+            shell.run("cd a d"),
+            Err(ShellError::Usage { usage: "cd [dir]" })
+        );
+        assert_eq!(
+            shell.run("cat b.txt"),
+            Err(ShellError::UnknownCommand {
+                command: "cat".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_ls_and_tree() {
+        let mut shell = example_shell();
+        assert_eq!(
+            shell.run("ls"),
+            Ok("dir a\ndir d\n14848514 b.txt\n8504156 c.dat\n".to_string())
+        );
+        assert_eq!(shell.run("ls a/h.lst"), Ok("62596 h.lst\n".to_string()));
+        assert_eq!(
+            shell.run("tree"),
+            Ok("- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+"
+            .to_string())
+        );
+        assert_eq!(
+            shell.run("tree /a/e"),
+            Ok("- e (dir)\n  - i (file, size=584)\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_du_and_find() {
+        let mut shell = example_shell();
+        assert_eq!(
+            shell.run("du"),
+            Ok("584\t/a/e\n94853\t/a\n24933642\t/d\n48381165\t/\n".to_string())
+        );
+        assert_eq!(
+            shell.run("du -h /a"),
+            Ok("584\t/a/e\n93K\t/a\n".to_string())
+        );
+        assert_eq!(shell.run("du -h d"), Ok("24M\t/d\n".to_string()));
+
+        assert_eq!(
+            shell.run("find -type d -size -100000"),
+            Ok("/a\n/a/e\n".to_string())
+        );
+        assert_eq!(
+            shell.run("find a -size +10k"),
+            Ok("/a\n/a/f\n/a/h.lst\n".to_string())
+        );
+        assert_eq!(
+            shell.run("find / -type f -size 584"),
+            Ok("/a/e/i\n".to_string())
+        );
+        assert_eq!(
+            shell.run("find -size 1X"),
+            Err(ShellError::InvalidSize {
+                size: "1X".to_string()
+            })
+        );
+        assert!(matches!(
+            shell.run("find -type"),
+            Err(ShellError::Usage { .. })
+        ));
+    }
+
+    #[test]
+    fn test_glob() {
+        let mut shell = example_shell();
+        assert_eq!(
+            shell.run("glob /**/*.l* -min 60k"),
+            Ok("file\t62596\t/a/h.lst\nfile\t8033020\t/d/d.log\n".to_string())
+        );
+        assert_eq!(
+            shell.run("glob ** -ext log -min 5M"),
+            Ok("file\t8033020\t/d/d.log\n".to_string())
+        );
+        shell.run("cd a/e").unwrap();
+        assert_eq!(
+            shell.run("glob .. -type d -max 100k"),
+            Ok("dir\t94853\t/a\n".to_string())
+        );
+        assert_eq!(
+            shell.run("glob */.."),
+            Err(ShellError::InvalidPattern {
+                pattern: "*/..".to_string()
+            })
+        );
+        assert_eq!(
+            shell.run("glob * -max 1X"),
+            Err(ShellError::InvalidSize {
+                size: "1X".to_string()
+            })
+        );
+        assert!(matches!(shell.run("glob"), Err(ShellError::Usage { .. })));
+    }
+
+    #[test]
+    fn test_mkdir_touch_rm() {
+        let mut shell = example_shell();
+        assert_eq!(
+            shell.run("mkdir a"),
+            Err(ShellError::AlreadyExists {
+                path: "a".to_string()
+            })
+        );
+        assert_eq!(
+            shell.run("mkdir x/y"),
+            Err(ShellError::NotFound {
+                path: "x".to_string()
+            })
+        );
+        assert_eq!(
+            shell.run("mkdir .."),
+            Err(ShellError::InvalidName {
+                path: "..".to_string()
+            })
+        );
+        assert_eq!(
+            shell.run("touch big a/x"),
+            Err(ShellError::InvalidSize {
+                size: "big".to_string()
+            })
+        );
+        assert_eq!(shell.run("touch 16 a/x"), Ok(String::new()));
+        assert_eq!(shell.filesystem().root().recursive_size(), 48381165 + 16);
+
+        assert_eq!(
+            shell.run("rm a"),
+            Err(ShellError::IsADirectory {
+                path: "a".to_string()
+            })
+        );
+        assert_eq!(shell.run("rm a/x"), Ok(String::new()));
+        assert_eq!(shell.run("cd a/e"), Ok(String::new()));
+        assert_eq!(shell.run("rm -r /a"), Ok(String::new()));
+        // The current directory was removed, back to its remaining parent
+        assert_eq!(shell.run("pwd"), Ok("/\n".to_string()));
+        assert_eq!(
+            shell.run("du /"),
+            Ok("24933642\t/d\n48286312\t/\n".to_string())
+        );
+        assert_eq!(
+            shell.run("rm -r /"),
+            Err(ShellError::InvalidTarget {
+                path: "/".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_mv() {
+        let mut shell = example_shell();
+        assert_eq!(shell.run("mv b.txt a/e"), Ok(String::new()));
+        assert_eq!(
+            shell.run("ls a/e"),
+            Ok("584 i\n14848514 b.txt\n".to_string())
+        );
+        assert_eq!(shell.run("mv a/e/b.txt d/renamed.txt"), Ok(String::new()));
+        assert_eq!(shell.run("mv d /a/e/d2"), Ok(String::new()));
+        assert_eq!(
+            shell.run("du"),
+            Ok("39782156\t/a/e/d2\n39782740\t/a/e\n39877009\t/a\n48381165\t/\n".to_string())
+        );
+        assert_eq!(
+            shell.run("mv a a/e/d2"),
+            Err(ShellError::InvalidTarget {
+                path: "a/e/d2".to_string()
+            })
+        );
+        assert_eq!(
+            shell.run("mv c.dat a/f"),
+            Err(ShellError::AlreadyExists {
+                path: "a/f".to_string()
+            })
+        );
+        assert_eq!(
+            shell.run("mv missing a"),
+            Err(ShellError::NotFound {
+                path: "missing".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_mv_kind_clash() {
+        // A directory onto a file of the same name
+        let mut shell = example_shell();
+        shell.run("mkdir x").unwrap();
+        shell.run("touch 5 a/x").unwrap();
+        assert_eq!(
+            shell.run("mv x a"),
+            Err(ShellError::AlreadyExists {
+                path: "/a/x".to_string()
+            })
+        );
+        assert_eq!(
+            shell.run("ls a"),
+            Ok("dir e\n29116 f\n2557 g\n62596 h.lst\n5 x\n".to_string())
+        );
+
+        // A file onto a directory of the same name
+        shell.run("touch 1 y").unwrap();
+        shell.run("mkdir d/y").unwrap();
+        assert_eq!(
+            shell.run("mv y d"),
+            Err(ShellError::AlreadyExists {
+                path: "/d/y".to_string()
+            })
+        );
+        assert_eq!(shell.run("glob /d/y"), Ok("dir\t0\t/d/y\n".to_string()));
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut shell = example_shell();
+        let (output, errors) = shell.replay(
+            "$ cd d
+$ pwd
+/d
+$ cd e
+$ ls
+4060174 j
+",
+        );
+        assert_eq!(
+            output,
+            "$ cd d\n$ pwd\n/d\n$ cd e\nerror: e: no such file or directory\n$ ls\n\
+             4060174 j\n8033020 d.log\n5626152 d.ext\n7214296 k\n"
+        );
+        assert_eq!(
+            errors,
+            vec![ReplayError {
+                line: 4,
+                error: ShellError::NotFound {
+                    path: "e".to_string()
+                }
+            }]
+        );
+    }
+
+    #[test]
+    fn test_repl() {
+        let mut shell = example_shell();
+        let mut output = Vec::new();
+        shell
+            .repl("cd a\nls e\nrm e\nexit\npwd\n".as_bytes(), &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "/ $ /a $ 584 i\n/a $ error: e: is a directory\n/a $ "
+        );
+    }
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(0), "0");
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(1536), "1.5K");
+        assert_eq!(human_size(94853), "93K");
+        assert_eq!(human_size(3 << 30), "3.0G");
+    }
+}
@@ -0,0 +1,343 @@
+use std::fmt;
+
+use crate::filesystem::FileSystem;
+
+#[derive(Debug, PartialEq)]
+pub enum TranscriptError {
+    UnknownCommand {
+        line: usize,
+        command: String,
+    },
+    // `cd` without exactly one argument, or `ls` with arguments
+    InvalidArguments {
+        line: usize,
+        command: String,
+    },
+    // A listing line that doesn't follow `ls`
+    UnexpectedOutput {
+        line: usize,
+    },
+    // Neither `dir <name>` nor `<size> <name>`
+    InvalidEntry {
+        line: usize,
+    },
+    InvalidSize {
+        line: usize,
+        size: String,
+    },
+    // `cd ..` from the root
+    AboveRoot {
+        line: usize,
+    },
+    // The same name listed, or entered, as both a file and a directory
+    KindConflict {
+        line: usize,
+        name: String,
+    },
+    // A file listed again with another size
+    SizeConflict {
+        line: usize,
+        name: String,
+        previous: usize,
+        size: usize,
+    },
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TranscriptError::UnknownCommand { line, command } => {
+                write!(f, "line {}: unknown command {}", line, command)
+            }
+            TranscriptError::InvalidArguments { line, command } => {
+                write!(f, "line {}: invalid arguments for {}", line, command)
+            }
+            TranscriptError::UnexpectedOutput { line } => {
+                write!(f, "line {}: output outside of ls", line)
+            }
+            TranscriptError::InvalidEntry { line } => write!(f, "line {}: invalid entry", line),
+            TranscriptError::InvalidSize { line, size } => {
+                write!(f, "line {}: invalid size {}", line, size)
+            }
+            TranscriptError::AboveRoot { line } => {
+                write!(f, "line {}: cd .. from the root", line)
+            }
+            TranscriptError::KindConflict { line, name } => {
+                write!(f, "line {}: {} is both a file and a directory", line, name)
+            }
+            TranscriptError::SizeConflict {
+                line,
+                name,
+                previous,
+                size,
+            } => write!(
+                f,
+                "line {}: {} has size {} but was listed with size {}",
+                line, name, size, previous
+            ),
+        }
+    }
+}
+
+struct Parser {
+    fs: FileSystem,
+    errors: Vec<TranscriptError>,
+    // Whether the lines that follow are the output of `ls`
+    listing: bool,
+}
+
+impl Parser {
+    fn command(&mut self, line: usize, command: &str) {
+        self.listing = false;
+        let args: Vec<&str> = command.split_whitespace().collect();
+        match args[..] {
+            ["cd", path] => self.cd(line, path),
+            ["ls"] => self.listing = true,
+            ["cd", ..] | ["ls", ..] => self.errors.push(TranscriptError::InvalidArguments {
+                line,
+                command: args[0].to_string(),
+            }),
+            _ => self.errors.push(TranscriptError::UnknownCommand {
+                line,
+                command: args.first().unwrap_or(&"").to_string(),
+            }),
+        }
+    }
+
+    // Follow the path one component at a time, creating the directories
+    // that weren't listed yet
+    fn cd(&mut self, line: usize, path: &str) {
+        if path.starts_with('/') {
+            self.fs.cd_root();
+        }
+        for name in path
+            .split('/')
+            .filter(|name| !name.is_empty() && *name != ".")
+        {
+            if name == ".." {
+                if self.fs.dir(self.fs.cwd()).parent().is_none() {
+                    self.errors.push(TranscriptError::AboveRoot { line });
+                }
+                self.fs.cd_parent();
+                continue;
+            }
+            if self.fs.cd(name).is_some() {
+                continue;
+            }
+            let cwd = self.fs.cwd();
+            if self.fs.dir(cwd).files().iter().any(|f| f.name == name) {
+                self.errors.push(TranscriptError::KindConflict {
+                    line,
+                    name: name.to_string(),
+                });
+                return;
+            }
+            let dir = self.fs.add_subdir(cwd, name);
+            self.fs.set_cwd(dir);
+        }
+    }
+
+    fn entry(&mut self, line: usize, entry: &str) {
+        let (kind, name) = match entry.split_once(' ') {
+            Some((kind, name)) if !name.trim().is_empty() => (kind, name.trim()),
+            _ => return self.errors.push(TranscriptError::InvalidEntry { line }),
+        };
+        let cwd = self.fs.cwd();
+        let dir = self.fs.dir(cwd);
+        let file = dir.files().iter().find(|f| f.name == name);
+
+        if kind == "dir" {
+            if file.is_some() {
+                return self.errors.push(TranscriptError::KindConflict {
+                    line,
+                    name: name.to_string(),
+                });
+            }
+            self.fs.add_subdir(cwd, name);
+            return;
+        }
+
+        let size = match kind.parse() {
+            Ok(size) => size,
+            Err(_) => {
+                return self.errors.push(TranscriptError::InvalidSize {
+                    line,
+                    size: kind.to_string(),
+                })
+            }
+        };
+        if dir.subdir(name).is_some() {
+            return self.errors.push(TranscriptError::KindConflict {
+                line,
+                name: name.to_string(),
+            });
+        }
+        match file {
+            Some(file) if file.size != size => {
+                let previous = file.size;
+                self.errors.push(TranscriptError::SizeConflict {
+                    line,
+                    name: name.to_string(),
+                    previous,
+                    size,
+                })
+            }
+            Some(_) => {}
+            None => self.fs.add_file(cwd, name, size),
+        }
+    }
+}
+
+// Rebuild the filesystem explored by a terminal transcript. Parsing goes
+// on after a problem, the filesystem keeps the first version of
+// conflicting entries. Blank lines are ignored.
+pub fn parse_transcript(input: &str) -> (FileSystem, Vec<TranscriptError>) {
+    let mut parser = Parser {
+        fs: FileSystem::new(),
+        errors: Vec::new(),
+        listing: false,
+    };
+
+    for (idx, text) in input.lines().enumerate() {
+        let line = idx + 1;
+        if text.trim().is_empty() {
+            continue;
+        }
+        if let Some(command) = text.strip_prefix('$') {
+            parser.command(line, command);
+        } else if parser.listing {
+            parser.entry(line, text.trim_end());
+        } else {
+            parser
+                .errors
+                .push(TranscriptError::UnexpectedOutput { line });
+        }
+    }
+    (parser.fs, parser.errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cd_into_unlisted_directories() {
+        let (fs, errors) = parse_transcript(
+            "$ cd a
+$ ls
+12 f
+$ cd /b/c
+$ ls
+dir d
+3 g
+$ cd ../../a
+$ ls
+dir h
+12 f
+",
+        );
+        assert_eq!(errors, vec![]);
+        let root = fs.root();
+        assert_eq!(root.recursive_size(), 15);
+        let a = root.subdir("a").unwrap();
+        assert_eq!(a.recursive_size(), 12);
+        assert!(a.subdir("h").is_some());
+        let c = root.subdir("b").unwrap().subdir("c").unwrap();
+        assert!(c.subdir("d").is_some());
+        assert_eq!(c.files()[0].name, "g");
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let (fs, errors) = parse_transcript(
+            "$ ls
+dir a
+10 b
+$ ls
+10 a
+20 b
+dir b
+$ cd b
+",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                TranscriptError::KindConflict {
+                    line: 5,
+                    name: "a".to_string()
+                },
+                TranscriptError::SizeConflict {
+                    line: 6,
+                    name: "b".to_string(),
+                    previous: 10,
+                    size: 20
+                },
+                TranscriptError::KindConflict {
+                    line: 7,
+                    name: "b".to_string()
+                },
+                TranscriptError::KindConflict {
+                    line: 8,
+                    name: "b".to_string()
+                },
+            ]
+        );
+        // The first listing wins
+        assert_eq!(fs.root().recursive_size(), 10);
+        assert!(fs.root().subdir("a").is_some());
+        assert_eq!(fs.cwd(), FileSystem::ROOT);
+    }
+
+    #[test]
+    fn test_malformed_lines() {
+        let (fs, errors) = parse_transcript(
+            "12 early
+$ cd
+$ ls -l
+$ rm a
+$
+$ ls
+dir
+12k big
+12
+5 ok
+$ cd ..
+",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                TranscriptError::UnexpectedOutput { line: 1 },
+                TranscriptError::InvalidArguments {
+                    line: 2,
+                    command: "cd".to_string()
+                },
+                TranscriptError::InvalidArguments {
+                    line: 3,
+                    command: "ls".to_string()
+                },
+                TranscriptError::UnknownCommand {
+                    line: 4,
+                    command: "rm".to_string()
+                },
+                TranscriptError::UnknownCommand {
+                    line: 5,
+                    command: "".to_string()
+                },
+                TranscriptError::InvalidEntry { line: 7 },
+                TranscriptError::InvalidSize {
+                    line: 8,
+                    size: "12k".to_string()
+                },
+                TranscriptError::InvalidEntry { line: 9 },
+                TranscriptError::AboveRoot { line: 11 },
+            ]
+        );
+        assert_eq!(fs.root().recursive_size(), 5);
+        assert_eq!(
+            errors[6].to_string(),
+            "line 8: invalid size 12k".to_string()
+        );
+    }
+}
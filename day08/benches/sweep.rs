@@ -0,0 +1,107 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ndarray::Array2;
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/sweep.rs"]
+mod sweep;
+
+// Previous implementation, scanning the four directions from every tree,
+// kept as a baseline for the sweeps
+fn scan_is_tree_visible(forest: &Array2<i32>, row: usize, col: usize) -> bool {
+    let tree_height = forest[[row, col]];
+    let (height, width) = forest.dim();
+    if row == 0 || row == height - 1 || col == 0 || col == width - 1 {
+        return true;
+    }
+
+    ((col + 1)..width).all(|c| forest[[row, c]] < tree_height)
+        || (0..col).all(|c| forest[[row, c]] < tree_height)
+        || (0..row).all(|r| forest[[r, col]] < tree_height)
+        || ((row + 1)..height).all(|r| forest[[r, col]] < tree_height)
+}
+
+fn scan_scenic_score(forest: &Array2<i32>, row: usize, col: usize) -> usize {
+    let tree_height = forest[[row, col]];
+    let (height, width) = forest.dim();
+    let distance = |trees: &mut dyn Iterator<Item = i32>| {
+        let mut score = 0;
+        for tree in trees {
+            score += 1;
+            if tree >= tree_height {
+                break;
+            }
+        }
+        score
+    };
+
+    distance(&mut ((col + 1)..width).map(|c| forest[[row, c]]))
+        * distance(&mut (0..col).rev().map(|c| forest[[row, c]]))
+        * distance(&mut (0..row).rev().map(|r| forest[[r, col]]))
+        * distance(&mut ((row + 1)..height).map(|r| forest[[r, col]]))
+}
+
+fn scan_visible_tree_count(forest: &Array2<i32>) -> usize {
+    forest
+        .indexed_iter()
+        .filter(|((row, col), _)| scan_is_tree_visible(forest, *row, *col))
+        .count()
+}
+
+fn scan_best_scenic_score(forest: &Array2<i32>) -> usize {
+    forest
+        .indexed_iter()
+        .map(|((row, col), _)| scan_scenic_score(forest, row, col))
+        .max()
+        .unwrap_or(0)
+}
+
+// Digits from a linear congruential generator, like the puzzle input
+fn random_forest(size: usize) -> Array2<i32> {
+    let mut seed: u64 = 42;
+    Array2::from_shape_simple_fn((size, size), || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+        (seed >> 33) as i32 % 10
+    })
+}
+
+// Heights growing towards the center, every scan reaches the edge
+fn pyramid_forest(size: usize) -> Array2<i32> {
+    Array2::from_shape_fn((size, size), |(row, col)| {
+        let to_edge = row.min(col).min(size - 1 - row).min(size - 1 - col);
+        to_edge as i32
+    })
+}
+
+fn bench_sweeps(c: &mut Criterion) {
+    let mut group = c.benchmark_group("forest");
+    group.sample_size(10);
+    let forests = [
+        ("random", 1000, random_forest(1000)),
+        ("pyramid", 1000, pyramid_forest(1000)),
+    ];
+    for (name, size, forest) in &forests {
+        let id = format!("{}/{}", name, size);
+        group.bench_with_input(
+            BenchmarkId::new("scan_visible", &id),
+            forest,
+            |b, forest| b.iter(|| scan_visible_tree_count(black_box(forest))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("sweep_visible", &id),
+            forest,
+            |b, forest| b.iter(|| sweep::visibility_mask(black_box(forest))),
+        );
+        group.bench_with_input(BenchmarkId::new("scan_scenic", &id), forest, |b, forest| {
+            b.iter(|| scan_best_scenic_score(black_box(forest)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("sweep_scenic", &id),
+            forest,
+            |b, forest| b.iter(|| sweep::scenic_scores(black_box(forest))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_sweeps);
+criterion_main!(benches);
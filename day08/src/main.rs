@@ -1,52 +1,110 @@
-use ndarray::{arr2, Array2};
+use std::fmt;
+
+use ndarray::Array2;
+
+mod sweep;
 
 fn main() {
     let input = include_str!("../input.txt");
 
-    println!("Part 1: {}", part1(input));
-    println!("Part 2: {}", part2(input));
+    for (part, answer) in [(1, part1(input)), (2, part2(input))] {
+        match answer {
+            Ok(answer) => println!("Part {}: {}", part, answer),
+            Err(error) => println!("Part {}: {}", part, error),
+        }
+    }
 }
 
 // 1. Parse string to get 2d matrix
 // 2. For each row col not on edge compute visibility
-fn part1(input: &str) -> usize {
-    let forest = parse_forest_to_matrix(input);
-    compute_visible_tree_count(&forest)
+fn part1(input: &str) -> Result<usize, ForestError> {
+    let forest = parse_forest_to_matrix(input)?;
+    Ok(compute_visible_tree_count(&forest))
+}
+
+#[derive(Debug, PartialEq)]
+enum ForestError {
+    // Lines are numbered from 1, columns from 0
+    InvalidTree {
+        line: usize,
+        column: usize,
+        character: char,
+    },
+    // A row whose length differs from the first row
+    RaggedRow {
+        line: usize,
+        len: usize,
+        expected: usize,
+    },
 }
 
-fn parse_forest_to_matrix(input: &str) -> ndarray::Array2<i32> {
-    let nested_matrix = input
-        .lines()
-        .filter(|l| !l.is_empty())
-        .map(|line| {
-            line.chars()
-                .filter_map(|c| c.to_digit(10))
-                .map(|c| c as i32)
-                .collect()
-        })
-        .collect::<Vec<Vec<i32>>>();
-
-    if nested_matrix.is_empty() {
-        return Array2::zeros((0, 0));
+impl fmt::Display for ForestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ForestError::InvalidTree {
+                line,
+                column,
+                character,
+            } => write!(
+                f,
+                "line {}: {:?} at column {} is not a tree height",
+                line, character, column
+            ),
+            ForestError::RaggedRow {
+                line,
+                len,
+                expected,
+            } => write!(f, "line {}: {} trees instead of {}", line, len, expected),
+        }
     }
+}
 
-    let mut matrix = Array2::zeros((nested_matrix.len(), nested_matrix[0].len()));
-    for (i, row) in nested_matrix.iter().enumerate() {
-        for (j, col) in row.iter().enumerate() {
-            matrix[[i, j]] = *col;
+// Blank lines are skipped, every other line is a row of digits and all
+// rows have the same length
+fn parse_forest_to_matrix(input: &str) -> Result<ndarray::Array2<i32>, ForestError> {
+    let mut heights = Vec::new();
+    let mut width = None;
+    let mut height = 0;
+
+    for (idx, line) in input.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        for (column, character) in line.chars().enumerate() {
+            let tree = character.to_digit(10).ok_or(ForestError::InvalidTree {
+                line: idx + 1,
+                column,
+                character,
+            })?;
+            heights.push(tree as i32);
+        }
+        let len = line.chars().count();
+        match width {
+            Some(expected) if expected != len => {
+                return Err(ForestError::RaggedRow {
+                    line: idx + 1,
+                    len,
+                    expected,
+                })
+            }
+            _ => width = Some(len),
         }
+        height += 1;
     }
 
-    matrix
+    Ok(Array2::from_shape_vec((height, width.unwrap_or(0)), heights).unwrap())
 }
 
+// Scan of the four directions from one tree, used to check the sweeps
+#[cfg(test)]
 fn is_tree_visible(forest: &ndarray::Array2<i32>, row: usize, col: usize) -> bool {
     let tree_height = forest[[row, col]];
     let forest_width = forest.shape()[1];
     let forest_height = forest.shape()[0];
 
     // Check is edge
-    if row == 0 || row == forest_width - 1 || col == 0 || col == forest_height - 1 {
+    if row == 0 || row == forest_height - 1 || col == 0 || col == forest_width - 1 {
         return true;
     }
 
@@ -102,18 +160,13 @@ fn is_tree_visible(forest: &ndarray::Array2<i32>, row: usize, col: usize) -> boo
 }
 
 fn compute_visible_tree_count(forest: &ndarray::Array2<i32>) -> usize {
-    let mut count = 0;
-    for row in 0..forest.shape()[0] {
-        for col in 0..forest.shape()[1] {
-            if is_tree_visible(forest, row, col) {
-                count += 1;
-            }
-        }
-    }
-
-    count
+    sweep::visibility_mask(forest)
+        .iter()
+        .filter(|visible| **visible)
+        .count()
 }
 
+#[cfg(test)]
 fn compute_scenic_score_of_tree(forest: &ndarray::Array2<i32>, row: usize, col: usize) -> usize {
     let tree_height = forest[[row, col]];
     let forest_width = forest.shape()[1];
@@ -163,27 +216,22 @@ fn compute_scenic_score_of_tree(forest: &ndarray::Array2<i32>, row: usize, col:
 }
 
 fn find_best_scenic_score(forest: &ndarray::Array2<i32>) -> usize {
-    let mut best_score = 0;
-    for row in 0..forest.shape()[0] {
-        for col in 0..forest.shape()[1] {
-            let score = compute_scenic_score_of_tree(forest, row, col);
-            if score > best_score {
-                best_score = score;
-            }
-        }
-    }
-
-    best_score
+    sweep::scenic_scores(forest)
+        .iter()
+        .copied()
+        .max()
+        .unwrap_or(0)
 }
 
-fn part2(input: &str) -> usize {
-    let forest = parse_forest_to_matrix(input);
-    find_best_scenic_score(&forest)
+fn part2(input: &str) -> Result<usize, ForestError> {
+    let forest = parse_forest_to_matrix(input)?;
+    Ok(find_best_scenic_score(&forest))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ndarray::arr2;
 
     static _EXAMPLE_INPUT: &str = r#"
 30373
@@ -194,7 +242,7 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(_EXAMPLE_INPUT), 21);
+        assert_eq!(part1(_EXAMPLE_INPUT), Ok(21));
     }
 
     fn create_forest_example_data() -> Array2<i32> {
@@ -211,8 +259,72 @@ mod tests {
     fn test_parse_forest_to_matrix() {
         assert_eq!(
             parse_forest_to_matrix(_EXAMPLE_INPUT),
-            create_forest_example_data()
+            Ok(create_forest_example_data())
+        );
+    }
+
+    #[test]
+    fn test_parse_irregular_forests() {
+        assert_eq!(parse_forest_to_matrix(""), Ok(Array2::zeros((0, 0))));
+        assert_eq!(parse_forest_to_matrix("\n\n"), Ok(Array2::zeros((0, 0))));
+        assert_eq!(
+            parse_forest_to_matrix("123\r\n456\r\n"),
+            Ok(arr2(&[[1, 2, 3], [4, 5, 6]]))
+        );
+        assert_eq!(
+            parse_forest_to_matrix("\n303\n2551\n"),
+            Err(ForestError::RaggedRow {
+                line: 3,
+                len: 4,
+                expected: 3
+            })
         );
+        assert_eq!(
+            parse_forest_to_matrix("303\n2a5\n"),
+            Err(ForestError::InvalidTree {
+                line: 2,
+                column: 1,
+                character: 'a'
+            })
+        );
+        assert_eq!(
+            parse_forest_to_matrix("30 3\n").unwrap_err().to_string(),
+            "line 1: ' ' at column 2 is not a tree height"
+        );
+    }
+
+    #[test]
+    fn test_rectangular_forests() {
+        // Wide: only the 16 trees on the edges are visible, the center 3
+        // sees two trees on each side
+        let wide = "3333333\n3313133\n3333333\n";
+        assert_eq!(part1(wide), Ok(16));
+        assert_eq!(part2(wide), Ok(4));
+        let forest = parse_forest_to_matrix(wide).unwrap();
+        assert!(!is_tree_visible(&forest, 1, 1));
+        assert!(!is_tree_visible(&forest, 1, 5));
+        assert!(is_tree_visible(&forest, 1, 6));
+
+        // Tall: the 9 sees 2 trees up and 3 down, and 1 on each side
+        let tall = "111\n101\n191\n101\n101\n101\n";
+        assert_eq!(part1(tall), Ok(15));
+        assert_eq!(part2(tall), Ok(6));
+        let forest = parse_forest_to_matrix(tall).unwrap();
+        assert!(!is_tree_visible(&forest, 1, 1));
+        assert!(is_tree_visible(&forest, 5, 1));
+        assert!(is_tree_visible(&forest, 4, 2));
+    }
+
+    #[test]
+    fn test_strips_and_empty_forest() {
+        // Every tree of a strip is on an edge, and sees nothing on one side
+        for strip in ["90519", "9\n0\n5\n1\n9"] {
+            assert_eq!(part1(strip), Ok(5));
+            assert_eq!(part2(strip), Ok(0));
+        }
+        assert_eq!(part1("7"), Ok(1));
+        assert_eq!(part1(""), Ok(0));
+        assert_eq!(part2(""), Ok(0));
     }
 
     #[test]
@@ -258,6 +370,37 @@ mod tests {
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(_EXAMPLE_INPUT), 8);
+        assert_eq!(part2(_EXAMPLE_INPUT), Ok(8));
+    }
+
+    #[test]
+    fn test_sweeps_match_scans() {
+        // Linear congruential generator, for reproducible forests
+        let mut seed: u64 = 42;
+        let mut next_height = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (seed >> 33) as i32 % 10
+        };
+        for shape in [
+            (1, 1),
+            (2, 2),
+            (10, 10),
+            (37, 37),
+            (1, 9),
+            (9, 1),
+            (3, 20),
+            (25, 4),
+        ] {
+            let forest = Array2::from_shape_simple_fn(shape, &mut next_height);
+            let mask = sweep::visibility_mask(&forest);
+            let scores = sweep::scenic_scores(&forest);
+            for ((row, col), visible) in mask.indexed_iter() {
+                assert_eq!(*visible, is_tree_visible(&forest, row, col));
+                assert_eq!(
+                    scores[[row, col]],
+                    compute_scenic_score_of_tree(&forest, row, col)
+                );
+            }
+        }
     }
 }
@@ -0,0 +1,138 @@
+use ndarray::{Array2, Zip};
+
+// Mark the trees of a line visible from either end. Each tree is compared
+// with the running maximum of the trees before it, so the line is read
+// once per direction.
+fn visible_line(heights: &[i32], visible: &mut [bool]) {
+    let mut mark = |idx: usize, tallest: &mut Option<i32>| {
+        if tallest.is_none_or(|tallest| heights[idx] > tallest) {
+            visible[idx] = true;
+            *tallest = Some(heights[idx]);
+        }
+    };
+    let mut tallest = None;
+    for idx in 0..heights.len() {
+        mark(idx, &mut tallest);
+    }
+    let mut tallest = None;
+    for idx in (0..heights.len()).rev() {
+        mark(idx, &mut tallest);
+    }
+}
+
+// Multiply the score of each tree of a line by the number of trees it
+// sees towards both ends, up to the first one at least as tall, in a
+// single pass. The stack keeps the trees that haven't met a tree at
+// least as tall yet, in decreasing height: the tree that pops one is the
+// end of its view forward, and the nearest tree left on the stack, or
+// the last equal one popped, is the end of its own view backward. Each
+// tree is pushed and popped once.
+fn scenic_line(heights: &[i32], scores: &mut [usize], stack: &mut Vec<usize>) {
+    stack.clear();
+    for (idx, height) in heights.iter().enumerate() {
+        let mut equal = None;
+        while let Some(&top) = stack.last() {
+            if heights[top] > *height {
+                break;
+            }
+            scores[top] *= idx - top;
+            equal = (heights[top] == *height).then_some(top);
+            stack.pop();
+        }
+        let blocker = equal.or(stack.last().copied());
+        scores[idx] *= idx - blocker.unwrap_or(0);
+        stack.push(idx);
+    }
+
+    // Trees that see up to the end of the line
+    let last = heights.len().saturating_sub(1);
+    for top in stack.drain(..) {
+        scores[top] *= last - top;
+    }
+}
+
+// Run `line` on the rows, then on the columns through a transposed copy
+// so that both are read as contiguous slices. Return the values from the
+// rows and from the columns, in the shape of the forest.
+fn sweep<T: Clone>(
+    forest: &Array2<i32>,
+    init: T,
+    mut line: impl FnMut(&[i32], &mut [T]),
+) -> (Array2<T>, Array2<T>) {
+    let mut sweep_rows = |forest: &Array2<i32>| {
+        let mut result = Array2::from_elem(forest.dim(), init.clone());
+        for (heights, mut values) in forest.rows().into_iter().zip(result.rows_mut()) {
+            line(heights.as_slice().unwrap(), values.as_slice_mut().unwrap());
+        }
+        result
+    };
+
+    let rows = sweep_rows(&forest.as_standard_layout().into_owned());
+    let columns = sweep_rows(&forest.t().as_standard_layout().into_owned());
+    (rows, columns.reversed_axes())
+}
+
+// Trees visible from outside the forest, in O(rows * cols)
+pub fn visibility_mask(forest: &Array2<i32>) -> Array2<bool> {
+    let (mut mask, columns) = sweep(forest, false, visible_line);
+    Zip::from(&mut mask)
+        .and(&columns)
+        .for_each(|visible, from_column| *visible |= *from_column);
+    mask
+}
+
+// Scenic score of every tree, in O(rows * cols)
+pub fn scenic_scores(forest: &Array2<i32>) -> Array2<usize> {
+    let mut stack = Vec::new();
+    let (mut scores, columns) = sweep(forest, 1, |heights, scores| {
+        scenic_line(heights, scores, &mut stack)
+    });
+    Zip::from(&mut scores)
+        .and(&columns)
+        .for_each(|score, from_column| *score *= *from_column);
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::arr2;
+
+    #[test]
+    fn test_lines() {
+        let mut visible = [false; 5];
+        visible_line(&[3, 0, 3, 7, 3], &mut visible);
+        assert_eq!(visible, [true, false, false, true, true]);
+
+        // Distances 0 1 2 3 1 to the start and 2 1 1 1 0 to the end
+        let mut scores = [1; 5];
+        scenic_line(&[3, 0, 3, 7, 3], &mut scores, &mut Vec::new());
+        assert_eq!(scores, [0, 1, 2, 3, 0]);
+        let mut scores = [1; 3];
+        scenic_line(&[5, 5, 5], &mut scores, &mut Vec::new());
+        assert_eq!(scores, [0, 1, 0]);
+
+        visible_line(&[], &mut []);
+        scenic_line(&[], &mut [], &mut Vec::new());
+    }
+
+    #[test]
+    fn test_sweeps() {
+        let forest = arr2(&[
+            [3, 0, 3, 7, 3],
+            [2, 5, 5, 1, 2],
+            [6, 5, 3, 3, 2],
+            [3, 3, 5, 4, 9],
+            [3, 5, 3, 9, 0],
+        ]);
+        let mask = visibility_mask(&forest);
+        assert_eq!(mask.iter().filter(|visible| **visible).count(), 21);
+        assert!(!mask[[1, 3]]);
+        assert!(mask[[3, 2]]);
+
+        let scores = scenic_scores(&forest);
+        assert_eq!(scores[[1, 2]], 4);
+        assert_eq!(scores[[3, 2]], 8);
+        assert_eq!(scores.iter().max(), Some(&8));
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::{self, Context};
+use interval::{Interval, IntervalSet};
 use itertools::Itertools;
 use regex::Regex;
 
@@ -10,16 +11,16 @@ fn main() {
 
 fn part1(input: &str, y: i64) -> usize {
     let sensors = parse_input(input).unwrap();
-    let mut intersections = get_possible_intersections(&sensors, y);
-    let merged_intersections = get_merged_intersections(&mut intersections);
+    let intersections = get_possible_intersections(&sensors, y);
+    let merged_intersections = get_merged_intersections(&intersections);
     get_intersection_sum(&merged_intersections) as usize - get_beacons_count_with_y(y, &sensors)
 }
 
 fn part2(input: &str, max: i64) -> Option<usize> {
     let sensors = parse_input(input).unwrap();
     for y in 0..max {
-        let mut intersections = get_possible_intersections(&sensors, y);
-        let merged_intersections = get_merged_intersections(&mut intersections);
+        let intersections = get_possible_intersections(&sensors, y);
+        let merged_intersections = get_merged_intersections(&intersections);
 
         for (_, x2) in &merged_intersections {
             if *x2 >= 0 && *x2 <= max {
@@ -67,30 +68,23 @@ fn get_possible_intersections(sensors: &[Sensor], y: i64) -> Vec<(i64, i64)> {
 /// The intersections are sorted by the x coordinate of the first point
 /// The first point of the intersection is the leftmost point of the intersection
 /// The second point of the intersection is the rightmost point of the intersection
-fn get_merged_intersections(intersections: &mut Vec<(i64, i64)>) -> Vec<(i64, i64)> {
-    intersections.sort_by_key(|(x, _)| *x);
-    let intersections: &[(i64, i64)] = intersections;
-    let mut results = Vec::new();
-
-    for (x1, x2) in intersections {
-        if results.is_empty() {
-            results.push((*x1, *x2));
-        } else {
-            let last_intersection = results.last_mut().unwrap();
-            if last_intersection.1 + 1 == *x1 {
-                last_intersection.1 = *x2;
-            } else if *x1 > last_intersection.1 {
-                results.push((*x1, *x2));
-            } else if *x2 > last_intersection.1 {
-                last_intersection.1 = *x2;
-            }
-        }
-    }
-    results
+fn get_merged_intersections(intersections: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    intersections
+        .iter()
+        .filter_map(|&(x1, x2)| Interval::new(x1, x2))
+        .collect::<IntervalSet<i64>>()
+        .intervals()
+        .iter()
+        .map(|interval| (interval.start, interval.end))
+        .collect()
 }
 
 fn get_intersection_sum(intersections: &[(i64, i64)]) -> i64 {
-    intersections.iter().map(|(x1, x2)| x2 - x1 + 1).sum()
+    intersections
+        .iter()
+        .filter_map(|&(x1, x2)| Interval::new(x1, x2))
+        .map(|interval| interval.len() as i64)
+        .sum()
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -301,28 +295,22 @@ Sensor at x=20, y=1: closest beacon is at x=15, y=3";
 
     #[test]
     fn test_get_merged_intersections() {
-        let mut intersections = get_intersections();
-        let merged_intersections = get_merged_intersections(&mut intersections);
+        let intersections = get_intersections();
+        let merged_intersections = get_merged_intersections(&intersections);
         assert_eq!(merged_intersections, vec![(-2, 24)]);
     }
 
     #[test]
     fn test_get_merged_intersections_2() {
-        let merged_intersections = get_merged_intersections(&mut vec![
-            (1, 2),
-            (3, 4),
-            (5, 6),
-            (6, 6),
-            (10, 13),
-            (11, 34),
-        ]);
+        let merged_intersections =
+            get_merged_intersections(&[(1, 2), (3, 4), (5, 6), (6, 6), (10, 13), (11, 34)]);
         assert_eq!(merged_intersections, vec![(1, 6), (10, 34)]);
     }
 
     #[test]
     fn test_get_intersection_sum() {
-        assert_eq!(get_intersection_sum(&vec![(-2, 24)]), 27);
-        assert_eq!(get_intersection_sum(&vec![(1, 6), (10, 34)]), 6 + 25);
+        assert_eq!(get_intersection_sum(&[(-2, 24)]), 27);
+        assert_eq!(get_intersection_sum(&[(1, 6), (10, 34)]), 6 + 25);
     }
 
     #[test]
@@ -0,0 +1,315 @@
+// Interval algebra over integer bounds, shared by the days working on
+// ranges of integers (day04 and day15).
+
+use std::ops::{Add, Sub};
+
+// Integer type that can be used as an interval bound
+pub trait Bound: Copy + Ord + Add<Output = Self> + Sub<Output = Self> {
+    const ZERO: Self;
+    const ONE: Self;
+
+    // The bound in a type wide enough that the distance between any two
+    // bounds can't overflow
+    fn widen(self) -> i128;
+}
+
+impl Bound for u64 {
+    const ZERO: u64 = 0;
+    const ONE: u64 = 1;
+
+    fn widen(self) -> i128 {
+        self as i128
+    }
+}
+
+impl Bound for i64 {
+    const ZERO: i64 = 0;
+    const ONE: i64 = 1;
+
+    fn widen(self) -> i128 {
+        self as i128
+    }
+}
+
+// Inclusive range of integers `start..=end`, an interval is never empty
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interval<T = u64> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T: Bound> Interval<T> {
+    // Return None if `start > end`
+    pub fn new(start: T, end: T) -> Option<Interval<T>> {
+        if start > end {
+            return None;
+        }
+        Some(Interval { start, end })
+    }
+
+    // Number of integers in the interval, as a u128 since the whole
+    // domain of the bound type has one more than its maximum. An interval
+    // is never empty, so there is no `is_empty`.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u128 {
+        (self.end.widen() - self.start.widen()) as u128 + 1
+    }
+
+    pub fn contains_value(&self, value: T) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    pub fn contains(&self, other: &Interval<T>) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Interval<T>) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn intersection(&self, other: &Interval<T>) -> Option<Interval<T>> {
+        Interval::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    // Overlapping or adjacent intervals can be merged into a single one.
+    // Written without `end + 1` so it can't overflow on the type bounds.
+    fn touches(&self, other: &Interval<T>) -> bool {
+        if self.end < other.start {
+            other.start - self.end == T::ONE
+        } else if other.end < self.start {
+            self.start - other.end == T::ONE
+        } else {
+            true
+        }
+    }
+}
+
+// Set of integers stored as sorted, disjoint and non adjacent intervals
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T = u64> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T: Bound> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet {
+            intervals: Vec::new(),
+        }
+    }
+}
+
+impl<T: Bound> IntervalSet<T> {
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet::default()
+    }
+
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    // Number of integers in the set
+    pub fn len(&self) -> u128 {
+        self.intervals.iter().map(|interval| interval.len()).sum()
+    }
+
+    pub fn insert(&mut self, interval: Interval<T>) {
+        // Intervals strictly before `interval` that can't be merged with it
+        let first = self
+            .intervals
+            .partition_point(|i| i.end < interval.start && !i.touches(&interval));
+        let merged_count = self.intervals[first..]
+            .iter()
+            .take_while(|i| i.touches(&interval))
+            .count();
+
+        let merged =
+            self.intervals[first..first + merged_count]
+                .iter()
+                .fold(interval, |merged, i| Interval {
+                    start: merged.start.min(i.start),
+                    end: merged.end.max(i.end),
+                });
+        self.intervals.splice(first..first + merged_count, [merged]);
+    }
+
+    pub fn contains_value(&self, value: T) -> bool {
+        self.intervals.iter().any(|i| i.contains_value(value))
+    }
+
+    pub fn contains(&self, interval: &Interval<T>) -> bool {
+        self.intervals.iter().any(|i| i.contains(interval))
+    }
+
+    pub fn overlaps(&self, interval: &Interval<T>) -> bool {
+        self.intervals.iter().any(|i| i.overlaps(interval))
+    }
+
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut union = self.clone();
+        for interval in &other.intervals {
+            union.insert(*interval);
+        }
+        union
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut intervals = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (&self.intervals[i], &other.intervals[j]);
+            if let Some(intersection) = a.intersection(b) {
+                intervals.push(intersection);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        // Intersections of disjoint sorted intervals are already disjoint and sorted,
+        // but they might be adjacent
+        intervals.into_iter().collect()
+    }
+
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut intervals = Vec::new();
+        for interval in &self.intervals {
+            let mut rest = Some(*interval);
+            for removed in other.intervals.iter().filter(|r| r.overlaps(interval)) {
+                let Some(current) = rest else {
+                    break;
+                };
+                if current.start < removed.start {
+                    intervals.push(Interval {
+                        start: current.start,
+                        end: removed.start - T::ONE,
+                    });
+                }
+                rest = if removed.end < current.end {
+                    Some(Interval {
+                        start: removed.end + T::ONE,
+                        end: current.end,
+                    })
+                } else {
+                    None
+                };
+            }
+            intervals.extend(rest);
+        }
+        IntervalSet { intervals }
+    }
+}
+
+impl<T: Bound> From<Interval<T>> for IntervalSet<T> {
+    fn from(interval: Interval<T>) -> Self {
+        IntervalSet {
+            intervals: vec![interval],
+        }
+    }
+}
+
+impl<T: Bound> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut sorted: Vec<_> = iter.into_iter().collect();
+        sorted.sort();
+
+        let mut intervals: Vec<Interval<T>> = Vec::with_capacity(sorted.len());
+        for interval in sorted {
+            match intervals.last_mut() {
+                Some(last) if last.touches(&interval) => last.end = last.end.max(interval.end),
+                _ => intervals.push(interval),
+            }
+        }
+        IntervalSet { intervals }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interval(start: u64, end: u64) -> Interval {
+        Interval::new(start, end).unwrap()
+    }
+
+    fn set(intervals: &[(u64, u64)]) -> IntervalSet {
+        intervals.iter().map(|&(s, e)| interval(s, e)).collect()
+    }
+
+    #[test]
+    fn test_interval() {
+        assert_eq!(Interval::new(3u64, 2), None);
+        assert_eq!(interval(2, 4).len(), 3);
+        assert_eq!(interval(6, 6).len(), 1);
+        assert!(interval(2, 8).contains(&interval(3, 7)));
+        assert!(!interval(3, 7).contains(&interval(2, 8)));
+        assert!(interval(5, 7).overlaps(&interval(7, 9)));
+        assert!(!interval(2, 3).overlaps(&interval(4, 5)));
+        assert_eq!(
+            interval(2, 6).intersection(&interval(4, 8)),
+            Some(interval(4, 6))
+        );
+        assert_eq!(interval(2, 3).intersection(&interval(4, 5)), None);
+        assert_eq!(interval(1, 1_000_000_000).len(), 1_000_000_000);
+        assert_eq!(interval(0, u64::MAX - 1).len(), u64::MAX as u128);
+        assert_eq!(interval(0, u64::MAX).len(), 1 << 64);
+        assert_eq!(Interval::new(i64::MIN, i64::MAX).unwrap().len(), 1 << 64);
+    }
+
+    #[test]
+    fn test_interval_set_merges() {
+        let merged = set(&[(12, 12), (2, 14), (2, 2), (16, 24), (14, 18)]);
+        assert_eq!(merged.intervals(), &[interval(2, 24)]);
+        assert_eq!(merged.len(), 23);
+
+        let merged = set(&[(1, 2), (3, 4), (5, 6), (6, 6), (10, 13), (11, 34)]);
+        assert_eq!(merged, set(&[(1, 6), (10, 34)]));
+        assert_eq!(merged.len(), 6 + 25);
+        assert!(merged.contains(&interval(12, 20)));
+        assert!(!merged.contains(&interval(5, 10)));
+        assert!(merged.overlaps(&interval(5, 10)));
+        assert!(!merged.contains_value(8));
+    }
+
+    #[test]
+    fn test_interval_set_insert() {
+        let mut intervals = IntervalSet::new();
+        for (start, end) in [(10, 12), (1, 2), (20, 30), (4, 5), (3, 3), (13, 19)] {
+            intervals.insert(interval(start, end));
+        }
+        assert_eq!(intervals, set(&[(1, 5), (10, 30)]));
+
+        intervals.insert(interval(0, u64::MAX));
+        assert_eq!(intervals, set(&[(0, u64::MAX)]));
+        assert_eq!(intervals.len(), 1 << 64);
+    }
+
+    #[test]
+    fn test_interval_set_operations() {
+        let a = set(&[(1, 5), (10, 20)]);
+        let b = set(&[(4, 11), (15, 16), (19, 25)]);
+        assert_eq!(a.union(&b), set(&[(1, 25)]));
+        assert_eq!(
+            a.intersection(&b),
+            set(&[(4, 5), (10, 11), (15, 16), (19, 20)])
+        );
+        assert_eq!(a.difference(&b), set(&[(1, 3), (12, 14), (17, 18)]));
+        assert_eq!(b.difference(&a), set(&[(6, 9), (21, 25)]));
+        assert_eq!(a.difference(&a), IntervalSet::new());
+        assert_eq!(a.difference(&IntervalSet::new()), a);
+    }
+
+    #[test]
+    fn test_signed_bounds() {
+        let merged: IntervalSet<i64> = [(-2, 2), (2, 14), (16, 24), (14, 15)]
+            .into_iter()
+            .map(|(s, e)| Interval::new(s, e).unwrap())
+            .collect();
+        assert_eq!(merged.intervals(), &[Interval { start: -2, end: 24 }]);
+        assert_eq!(merged.len(), 27);
+    }
+}